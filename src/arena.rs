@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision_detection::Ccd,
+    controls::{Action, ActionState},
+    damage::DamageEvent,
+    despawn::MaxRange,
+    gamestate::Pause,
+    interpolation::Interpolated,
+    movement::{self, Velocity},
+    physics::RigidBody,
+    schedule::InGameSet,
+    status::Status,
+};
+
+const ARENA_HALF_EXTENTS: Vec3 = Vec3::new(100., 50., 100.);
+// how much speed is kept bouncing off a wall, for the entities without a rigid body
+const WALL_RESTITUTION: f32 = 0.8;
+const BOUNDARY_DAMAGE: u32 = 1;
+const BOUNDARY_DAMAGE_INTERVAL_SECONDS: f32 = 1.0;
+const WRAP_COLOR: Color = Color::CYAN;
+const WALLS_COLOR: Color = Color::WHITE;
+const DAMAGING_COLOR: Color = Color::RED;
+
+/// The play area, a box centered on the origin. Press the key 'B' to switch the mode.
+#[derive(Resource, Debug)]
+pub struct Arena {
+    pub half_extents: Vec3,
+    pub mode: ArenaMode,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            half_extents: ARENA_HALF_EXTENTS,
+            mode: ArenaMode::default(),
+        }
+    }
+}
+
+impl Arena {
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.abs().cmple(self.half_extents).all()
+    }
+}
+
+/// What happens to the moving objects leaving the arena.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaMode {
+    /// They come back in on the opposite side.
+    #[default]
+    Wrap,
+    /// They bounce off the boundary.
+    Walls,
+    /// They can leave, but lose health for as long as they are outside.
+    Damaging,
+}
+
+/// The source of the damage dealt outside a damaging arena.
+#[derive(Component, Debug)]
+pub struct ArenaBoundary;
+
+#[derive(Resource, Debug)]
+pub struct BoundaryDamageTimer {
+    pub timer: Timer,
+}
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arena>()
+            .insert_resource(BoundaryDamageTimer {
+                timer: Timer::from_seconds(BOUNDARY_DAMAGE_INTERVAL_SECONDS, TimerMode::Repeating),
+            })
+            .add_systems(Startup, spawn_arena_boundary)
+            .add_systems(Update, (switch_arena_mode, draw_arena_boundary))
+            .add_systems(
+                FixedUpdate,
+                (confine_to_arena, boundary_damage)
+                    .chain()
+                    .after(movement::update_position)
+                    .in_set(InGameSet::EntityUpdates)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            );
+    }
+}
+
+fn spawn_arena_boundary(mut commands: Commands) {
+    commands.spawn((Name::new("ArenaBoundary"), ArenaBoundary));
+}
+
+fn switch_arena_mode(mut arena: ResMut<Arena>, actions: Res<ActionState>) {
+    if !actions.just_pressed(Action::SwitchArenaMode) {
+        return;
+    }
+    arena.mode = match arena.mode {
+        ArenaMode::Wrap => ArenaMode::Walls,
+        ArenaMode::Walls => ArenaMode::Damaging,
+        ArenaMode::Damaging => ArenaMode::Wrap,
+    };
+    info!("Switched the arena to {:?}", arena.mode);
+}
+
+fn draw_arena_boundary(mut gizmos: Gizmos, arena: Res<Arena>) {
+    let color = match arena.mode {
+        ArenaMode::Wrap => WRAP_COLOR,
+        ArenaMode::Walls => WALLS_COLOR,
+        ArenaMode::Damaging => DAMAGING_COLOR,
+    };
+    gizmos.cuboid(Transform::from_scale(arena.half_extents * 2.0), color);
+}
+
+#[allow(clippy::type_complexity)]
+fn confine_to_arena(
+    mut query: Query<(
+        &mut Transform,
+        &mut Velocity,
+        Option<&mut Interpolated>,
+        Option<&mut Ccd>,
+        Option<&mut MaxRange>,
+        Option<&RigidBody>,
+    )>,
+    arena: Res<Arena>,
+) {
+    let half_extents = arena.half_extents;
+    for (mut transform, mut velocity, interpolated, ccd, max_range, body) in query.iter_mut() {
+        let translation = transform.translation;
+        match arena.mode {
+            ArenaMode::Wrap => {
+                if arena.contains(translation) {
+                    continue;
+                }
+                let size = half_extents * 2.0;
+                transform.translation =
+                    (translation + half_extents).rem_euclid(size) - half_extents;
+                // don't draw it sliding across the whole arena
+                if let Some(mut interpolated) = interpolated {
+                    interpolated.teleport();
+                }
+                // nor sweep it across the arena, hitting everything along the way
+                if let Some(mut ccd) = ccd {
+                    ccd.teleport();
+                }
+                // and keep counting the range travelled, not the jump
+                if let Some(mut max_range) = max_range {
+                    max_range.origin += transform.translation - translation;
+                }
+            }
+            ArenaMode::Walls => {
+                let restitution = body.map_or(WALL_RESTITUTION, |body| body.restitution);
+                transform.translation = translation.clamp(-half_extents, half_extents);
+                for axis in 0..3 {
+                    let outside = translation[axis].abs() > half_extents[axis];
+                    // only bounce if still moving outwards, not while coming back in
+                    let moving_out = velocity.value[axis] * translation[axis] > 0.0;
+                    if outside && moving_out {
+                        velocity.value[axis] *= -restitution;
+                    }
+                }
+            }
+            ArenaMode::Damaging => {}
+        }
+    }
+}
+
+fn boundary_damage(
+    mut damage_events: EventWriter<DamageEvent>,
+    mut damage_timer: ResMut<BoundaryDamageTimer>,
+    query: Query<(Entity, &Transform), With<Status>>,
+    boundary_query: Query<Entity, With<ArenaBoundary>>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    if arena.mode != ArenaMode::Damaging {
+        damage_timer.timer.reset();
+        return;
+    }
+    damage_timer.timer.tick(time.delta());
+    if !damage_timer.timer.just_finished() {
+        return;
+    }
+    let Ok(boundary) = boundary_query.get_single() else {
+        return;
+    };
+
+    for (entity, transform) in query.iter() {
+        if !arena.contains(transform.translation) {
+            damage_events.send(DamageEvent {
+                target: entity,
+                source: boundary,
+                amount: BOUNDARY_DAMAGE,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn arena_world(mode: ArenaMode) -> World {
+        let mut world = World::new();
+        world.insert_resource(Arena {
+            half_extents: Vec3::splat(100.),
+            mode,
+        });
+        world
+    }
+
+    #[test]
+    fn wrapping_keeps_the_range_travelled() {
+        let mut world = arena_world(ArenaMode::Wrap);
+        let projectile = world
+            .spawn((
+                Transform::from_xyz(101., 0., 0.),
+                Velocity::new(Vec3::X),
+                MaxRange::new(Vec3::new(50., 0., 0.), 150.),
+            ))
+            .id();
+
+        world.run_system_once(confine_to_arena);
+
+        let translation = world.get::<Transform>(projectile).unwrap().translation;
+        assert!(translation.abs_diff_eq(Vec3::new(-99., 0., 0.), 1e-4));
+        let origin = world.get::<MaxRange>(projectile).unwrap().origin;
+        assert!((translation.distance(origin) - 51.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn wrapping_comes_back_in_on_the_opposite_side() {
+        let mut world = arena_world(ArenaMode::Wrap);
+        let entity = world
+            .spawn((Transform::from_xyz(-102., 30., 0.), Velocity::new(-Vec3::X)))
+            .id();
+
+        world.run_system_once(confine_to_arena);
+
+        let translation = world.get::<Transform>(entity).unwrap().translation;
+        assert!(translation.abs_diff_eq(Vec3::new(98., 30., 0.), 1e-4));
+        assert_eq!(world.get::<Velocity>(entity).unwrap().value, -Vec3::X);
+    }
+
+    #[test]
+    fn walls_bounce_only_what_is_moving_out() {
+        let mut world = arena_world(ArenaMode::Walls);
+        let leaving = world
+            .spawn((
+                Transform::from_xyz(105., 0., 0.),
+                Velocity::new(Vec3::new(10., 0., 5.)),
+            ))
+            .id();
+        let coming_back = world
+            .spawn((
+                Transform::from_xyz(0., 0., -105.),
+                Velocity::new(Vec3::new(0., 0., 10.)),
+            ))
+            .id();
+        let bouncy = world
+            .spawn((
+                Transform::from_xyz(0., 105., 0.),
+                Velocity::new(Vec3::new(0., 10., 0.)),
+                RigidBody::new(1.0).with_restitution(1.0),
+            ))
+            .id();
+
+        world.run_system_once(confine_to_arena);
+
+        let translation = world.get::<Transform>(leaving).unwrap().translation;
+        assert_eq!(translation, Vec3::new(100., 0., 0.));
+        let velocity = world.get::<Velocity>(leaving).unwrap().value;
+        assert!(velocity.abs_diff_eq(Vec3::new(-10. * WALL_RESTITUTION, 0., 5.), 1e-5));
+        let velocity = world.get::<Velocity>(coming_back).unwrap().value;
+        assert_eq!(velocity, Vec3::new(0., 0., 10.));
+        let velocity = world.get::<Velocity>(bouncy).unwrap().value;
+        assert_eq!(velocity, Vec3::new(0., -10., 0.));
+    }
+
+    #[test]
+    fn damaging_arena_hurts_what_is_outside() {
+        let mut world = arena_world(ArenaMode::Damaging);
+        world.init_resource::<Events<DamageEvent>>();
+        world.insert_resource(BoundaryDamageTimer {
+            timer: Timer::from_seconds(BOUNDARY_DAMAGE_INTERVAL_SECONDS, TimerMode::Repeating),
+        });
+        let mut time = Time::<()>::default();
+        time.advance_by(std::time::Duration::from_secs_f32(
+            BOUNDARY_DAMAGE_INTERVAL_SECONDS,
+        ));
+        world.insert_resource(time);
+        let boundary = world.spawn(ArenaBoundary).id();
+        let outside = world
+            .spawn((Transform::from_xyz(150., 0., 0.), Status::new(3, 0)))
+            .id();
+        world.spawn((Transform::IDENTITY, Status::new(3, 0)));
+
+        world.run_system_once(boundary_damage);
+
+        let events = world.resource::<Events<DamageEvent>>();
+        let hits: Vec<_> = events
+            .get_reader()
+            .read(events)
+            .map(|event| (event.target, event.source, event.amount))
+            .collect();
+        assert_eq!(hits, vec![(outside, boundary, BOUNDARY_DAMAGE)]);
+    }
+}
@@ -3,8 +3,9 @@ use bevy::prelude::*;
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
     pub asteroid: Handle<Scene>,
-    pub spaceship: Handle<Scene>,
+    pub spaceships: Vec<Handle<Scene>>,
     pub missiles: Handle<Scene>,
+    pub planets: Vec<Handle<Scene>>,
 }
 
 pub struct AssetLoaderPlugin;
@@ -19,7 +20,16 @@ impl Plugin for AssetLoaderPlugin {
 fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
     *scene_assets = SceneAssets {
         asteroid: asset_server.load("SpaceGLB/Planet-4NxxeyYMPJ.glb#Scene0"),
-        spaceship: asset_server.load("SpaceGLB/Spaceship.glb#Scene0"),
+        spaceships: vec![
+            asset_server.load("SpaceGLB/Spaceship.glb#Scene0"),
+            asset_server.load("SpaceGLB/Spaceship-u105mYHLHU.glb#Scene0"),
+            asset_server.load("SpaceGLB/Spaceship-Jqfed124pQ.glb#Scene0"),
+            asset_server.load("SpaceGLB/Spaceship-VSxUAFhzbA.glb#Scene0"),
+        ],
         missiles: asset_server.load("SpaceGLB/Bullets Pickup.glb#Scene0"),
+        planets: vec![
+            asset_server.load("SpaceGLB/Planet-5zzi8WUMXj.glb#Scene0"),
+            asset_server.load("SpaceGLB/Planet-hKZtOOMadH.glb#Scene0"),
+        ],
     };
 }
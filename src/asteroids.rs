@@ -1,8 +1,14 @@
 use crate::asset_loader::SceneAssets;
-use crate::collision_detection::Collider;
+use crate::collision_detection::{Collider, CollisionLayer};
+use crate::damage::{Damage, DeathEvent, Team};
 use crate::gamestate::Pause;
-use crate::movement::{Acceleration, MovingObjectBundle, Velocity};
+use crate::interpolation::Interpolated;
+use crate::movement::{
+    Acceleration, AngularVelocity, Gravity, MaxSpeed, MovingObjectBundle, Velocity,
+};
+use crate::physics::RigidBody;
 use crate::schedule::InGameSet;
+use crate::spaceship::{FlightDimensions, Spaceship};
 use crate::status::Status;
 use bevy::prelude::*;
 use rand::Rng;
@@ -12,10 +18,24 @@ const VELOCITY_SCALAR: f32 = 5.0;
 const ACCELERATION_SCALAR: f32 = 1.0;
 const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
 const SPAWN_RANGE_Z: Range<f32> = 0.0..25.0;
+// only used when flying in 3D, otherwise everything stays in the XZ plane
+const SPAWN_RANGE_Y: Range<f32> = -25.0..25.0;
 const SPAWN_HEALTH_RANGE: Range<u32> = 1..3;
 const SPAWN_TIME_SECONDS: f32 = 1.0;
-const ASTEROID_ROTATION_SPEED: f32 = 2.5;
+const SPIN_SPEED_RANGE: Range<f32> = 1.0..4.0;
+// keeps the constant acceleration from speeding them up forever
+const MAX_SPEED: f32 = 10.0;
 const RADIUS: f32 = 2.5;
+const DAMAGE: u32 = 1;
+// mass per unit of volume, the asteroids are all made of the same rock
+const DENSITY: f32 = 1.0;
+const RESTITUTION: f32 = 0.8;
+// asteroids of this generation are too small to split any further
+const MAX_SPLIT_GENERATION: u32 = 2;
+const FRAGMENT_COUNT_RANGE: Range<u32> = 2..4;
+// each generation is this much smaller than its parent
+const FRAGMENT_SCALE: f32 = 0.6;
+const FRAGMENT_SPREAD_SPEED: f32 = 4.0;
 
 pub struct AsteroidsPlugin;
 
@@ -25,16 +45,27 @@ impl Plugin for AsteroidsPlugin {
             timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
         })
         .add_systems(
-            Update,
-            (spawn_asteroids, rotate_asteriod)
+            FixedUpdate,
+            spawn_asteroids
                 .in_set(InGameSet::EntityUpdates)
                 .run_if(|pause: Res<Pause>| !pause.is_paused),
+        )
+        .add_systems(
+            FixedUpdate,
+            split_asteroids
+                .in_set(InGameSet::DespawnEntities)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
         );
     }
 }
 
 #[derive(Component, Debug)]
-pub struct Asteroid;
+pub struct Asteroid {
+    /// 0 for the asteroids spawned by the timer, +1 for each split.
+    pub generation: u32,
+    /// The health it spawned with, its fragments get half of it.
+    pub health: u32,
+}
 
 #[derive(Resource, Debug)]
 pub struct SpawnTimer {
@@ -46,6 +77,7 @@ fn spawn_asteroids(
     mut spawn_timer: ResMut<SpawnTimer>,
     time: Res<Time>,
     scene_assets: Res<SceneAssets>,
+    flight_dimensions: Res<FlightDimensions>,
 ) {
     spawn_timer.timer.tick(time.delta());
     if !spawn_timer.timer.just_finished() {
@@ -53,14 +85,25 @@ fn spawn_asteroids(
     }
 
     let mut rng = rand::thread_rng();
+    // flatten everything into the XZ plane unless flying in 3D
+    let y_scale = match *flight_dimensions {
+        FlightDimensions::Planar => 0.0,
+        FlightDimensions::Full3d => 1.0,
+    };
     let translation = Vec3::new(
         rng.gen_range(SPAWN_RANGE_X),
-        0.0,
+        rng.gen_range(SPAWN_RANGE_Y) * y_scale,
         rng.gen_range(SPAWN_RANGE_Z),
     );
 
-    let mut random_unit_vector =
-        || Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0)).normalize_or_zero();
+    let mut random_unit_vector = || {
+        Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0) * y_scale,
+            rng.gen_range(-1.0..1.0),
+        )
+        .normalize_or_zero()
+    };
     let velocity = random_unit_vector() * VELOCITY_SCALAR;
     let acceleration = random_unit_vector() * ACCELERATION_SCALAR;
 
@@ -68,24 +111,144 @@ fn spawn_asteroids(
 
     let score = 2 * health - 1;
 
+    spawn_asteroid(
+        &mut commands,
+        &scene_assets,
+        translation,
+        velocity,
+        acceleration,
+        Status::new(health, score),
+        0,
+    );
+}
+
+fn split_asteroids(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    query: Query<(&Asteroid, &Status, &Transform, &Velocity, &Acceleration)>,
+    killer_query: Query<&Team, Without<Spaceship>>,
+    scene_assets: Res<SceneAssets>,
+    flight_dimensions: Res<FlightDimensions>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in death_events.read() {
+        let Ok((asteroid, status, transform, velocity, acceleration)) = query.get(event.entity)
+        else {
+            continue;
+        };
+        // only projectiles split asteroids. Rammed asteroids are pulverised, otherwise the
+        // fragments would hit the spaceship again, and so are the ones crashing into a
+        // planet or burnt by the spaceship's laser.
+        let shot = matches!(killer_query.get(event.killer), Ok(Team::Player));
+        if asteroid.generation >= MAX_SPLIT_GENERATION || !shot {
+            continue;
+        }
+
+        let generation = asteroid.generation + 1;
+        let fragments = rng.gen_range(FRAGMENT_COUNT_RANGE);
+        let offset_angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        // the fragments fly apart in the plane, or in a randomly tilted one in 3D
+        let tilt = match *flight_dimensions {
+            FlightDimensions::Planar => Quat::IDENTITY,
+            FlightDimensions::Full3d => {
+                let normal = Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .try_normalize()
+                .unwrap_or(Vec3::Y);
+                Quat::from_rotation_arc(Vec3::Y, normal)
+            }
+        };
+        for i in 0..fragments {
+            // spread the fragments evenly around the parent
+            let angle = offset_angle + std::f32::consts::TAU * i as f32 / fragments as f32;
+            let direction = tilt * Vec3::new(angle.cos(), 0., angle.sin());
+            spawn_asteroid(
+                &mut commands,
+                &scene_assets,
+                transform.translation + direction * radius_of(generation),
+                velocity.value + direction * FRAGMENT_SPREAD_SPEED,
+                acceleration.value,
+                fragment_status(asteroid.health, status.score),
+                generation,
+            );
+        }
+    }
+}
+
+fn spawn_asteroid(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    translation: Vec3,
+    velocity: Vec3,
+    acceleration: Vec3,
+    status: Status,
+    generation: u32,
+) {
+    let radius = radius_of(generation);
+    // fragments are smaller versions of the same model
+    let scale = radius / RADIUS;
+    let health = status.health;
+
+    let mut rng = rand::thread_rng();
+    let spin_axis = Vec3::new(
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+    )
+    .normalize_or_zero();
+    let spin = spin_axis * rng.gen_range(SPIN_SPEED_RANGE);
+
     commands.spawn((
         MovingObjectBundle {
             velocity: Velocity::new(velocity),
             acceleration: Acceleration::new(acceleration),
-            collider: Collider::new(RADIUS),
-            status: Status::new(health, score),
+            gravity: Gravity::default(),
+            collider: Collider::new(radius).with_layers(
+                CollisionLayer::ENVIRONMENT,
+                CollisionLayer::PLAYER
+                    | CollisionLayer::PLAYER_PROJECTILE
+                    | CollisionLayer::ENVIRONMENT,
+            ),
+            status,
             model: SceneBundle {
                 scene: scene_assets.asteroid.clone(),
-                transform: Transform::from_translation(translation),
+                transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
                 ..default()
             },
+            interpolated: Interpolated::default(),
         },
-        Asteroid,
+        AngularVelocity::new(spin),
+        MaxSpeed::new(MAX_SPEED),
+        RigidBody::new(DENSITY * radius.powi(3)).with_restitution(RESTITUTION),
+        Damage::new(DAMAGE),
+        Team::Enemy,
+        Asteroid { generation, health },
     ));
 }
 
-fn rotate_asteriod(mut query: Query<&mut Transform, With<Asteroid>>, time: Res<Time>) {
-    for mut transform in query.iter_mut() {
-        transform.rotate_local_z(ASTEROID_ROTATION_SPEED * time.delta_seconds());
+fn radius_of(generation: u32) -> f32 {
+    RADIUS * FRAGMENT_SCALE.powi(generation as i32)
+}
+
+/// Half the parent's health and score, so that smaller means weaker and worth less.
+fn fragment_status(parent_health: u32, parent_score: u32) -> Status {
+    Status::new((parent_health / 2).max(1), (parent_score / 2).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragments_are_weaker_and_worth_less() {
+        let status = fragment_status(4, 7);
+        assert_eq!((status.health, status.score), (2, 3));
+        // but never worthless
+        let status = fragment_status(1, 1);
+        assert_eq!((status.health, status.score), (1, 1));
     }
 }
@@ -0,0 +1,122 @@
+use std::ops::Range;
+
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+    utils::Instant,
+};
+use rand::Rng;
+
+use crate::{
+    collision_detection::{Collider, SpatialHash},
+    movement::{Acceleration, Velocity},
+    schedule::InGameSet,
+};
+
+const BENCHMARK_COLLIDERS: usize = 3000;
+const BENCHMARK_RANGE_X: Range<f32> = -50.0..50.0;
+const BENCHMARK_RANGE_Y: Range<f32> = -10.0..10.0;
+const BENCHMARK_RANGE_Z: Range<f32> = -60.0..20.0;
+const BENCHMARK_RADIUS: Range<f32> = 0.5..3.0;
+const BENCHMARK_SPEED: f32 = 2.0;
+const BENCHMARK_REPORT_SECONDS: f32 = 2.0;
+
+/// Stress scene for the collision broadphase, enabled by the `benchmark` feature.
+///
+/// Spawns thousands of invisible colliders and regularly compares the spatial hash
+/// against the brute force pair check, both in timing and in results.
+pub struct BenchmarkPlugin;
+
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
+            .insert_resource(BenchmarkTimer {
+                timer: Timer::from_seconds(BENCHMARK_REPORT_SECONDS, TimerMode::Repeating),
+            })
+            .add_systems(Startup, spawn_benchmark_colliders)
+            .add_systems(
+                Update,
+                report_broadphase.after(InGameSet::CollisionDetection),
+            );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct BenchmarkCollider;
+
+#[derive(Resource, Debug)]
+struct BenchmarkTimer {
+    timer: Timer,
+}
+
+fn spawn_benchmark_colliders(mut commands: Commands) {
+    let mut rng = rand::thread_rng();
+    for _ in 0..BENCHMARK_COLLIDERS {
+        let translation = Vec3::new(
+            rng.gen_range(BENCHMARK_RANGE_X),
+            rng.gen_range(BENCHMARK_RANGE_Y),
+            rng.gen_range(BENCHMARK_RANGE_Z),
+        );
+        let velocity = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        ) * BENCHMARK_SPEED;
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(translation)),
+            Velocity::new(velocity),
+            Acceleration::new(Vec3::ZERO),
+            Collider::new(rng.gen_range(BENCHMARK_RADIUS)),
+            BenchmarkCollider,
+        ));
+    }
+}
+
+fn report_broadphase(
+    query: Query<(Entity, &GlobalTransform, &Collider)>,
+    mut benchmark_timer: ResMut<BenchmarkTimer>,
+    time: Res<Time>,
+) {
+    benchmark_timer.timer.tick(time.delta());
+    if !benchmark_timer.timer.just_finished() {
+        return;
+    }
+
+    let start = Instant::now();
+    let mut brute_force_pairs = 0;
+    for (i, (_, transform_a, collider_a)) in query.iter().enumerate() {
+        let (_, rotation_a, position_a) = transform_a.to_scale_rotation_translation();
+        for (_, transform_b, collider_b) in query.iter().skip(i + 1) {
+            let (_, rotation_b, position_b) = transform_b.to_scale_rotation_translation();
+            if collider_a.interacts_with(collider_b)
+                && collider_a
+                    .contact(position_a, rotation_a, collider_b, position_b, rotation_b)
+                    .is_some()
+            {
+                brute_force_pairs += 1;
+            }
+        }
+    }
+    let brute_force_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut spatial_hash = SpatialHash::default();
+    for (entity, transform, collider) in query.iter() {
+        let (_, rotation, position) = transform.to_scale_rotation_translation();
+        spatial_hash.insert(entity, position, rotation, collider);
+    }
+    let spatial_hash_pairs = spatial_hash.colliding_pairs().len();
+    let spatial_hash_time = start.elapsed();
+
+    info!(
+        "{} colliders: brute force {} pairs in {:?}, spatial hash {} pairs in {:?}",
+        query.iter().len(),
+        brute_force_pairs,
+        brute_force_time,
+        spatial_hash_pairs,
+        spatial_hash_time,
+    );
+    if brute_force_pairs != spatial_hash_pairs {
+        warn!("Spatial hash and brute force disagree on the colliding pairs!");
+    }
+}
@@ -0,0 +1,547 @@
+use bevy::prelude::*;
+
+// the segment against box search shrinks its interval to this fraction each step
+const GOLDEN_RATIO: f32 = 0.618_034;
+const SEGMENT_BOX_ITERATIONS: usize = 32;
+// a model this many times longer than wide gets a capsule, otherwise a box
+const CAPSULE_ASPECT_RATIO: f32 = 2.0;
+
+/// The shape of a collider, in the entity's local space (scale is ignored).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColliderShape {
+    Sphere {
+        radius: f32,
+    },
+    /// Every point within `radius` of the segment from `start` to `end`.
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+    /// A box, oriented like the entity.
+    Cuboid {
+        center: Vec3,
+        half_extents: Vec3,
+    },
+    /// Several spheres as `(offset, radius)`, for shapes no single primitive fits.
+    Compound(Vec<(Vec3, f32)>),
+}
+
+impl ColliderShape {
+    /// Picks a capsule for elongated bounding boxes and a cuboid for the others.
+    pub fn fit_aabb(min: Vec3, max: Vec3) -> Self {
+        let center = (min + max) / 2.0;
+        let half_extents = (max - min) / 2.0;
+
+        let mut axes = [
+            (half_extents.x, Vec3::X),
+            (half_extents.y, Vec3::Y),
+            (half_extents.z, Vec3::Z),
+        ];
+        axes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let [(longest, axis), (second, _), _] = axes;
+
+        if longest >= second * CAPSULE_ASPECT_RATIO {
+            let half_length = longest - second;
+            Self::Capsule {
+                start: center - axis * half_length,
+                end: center + axis * half_length,
+                radius: second,
+            }
+        } else {
+            Self::Cuboid {
+                center,
+                half_extents,
+            }
+        }
+    }
+
+    /// The radius of a sphere around the entity's origin containing the whole shape.
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            Self::Sphere { radius } => *radius,
+            Self::Capsule { start, end, radius } => start.length().max(end.length()) + radius,
+            Self::Cuboid {
+                center,
+                half_extents,
+            } => center.length() + half_extents.length(),
+            Self::Compound(spheres) => spheres
+                .iter()
+                .map(|(offset, radius)| offset.length() + radius)
+                .fold(0.0, f32::max),
+        }
+    }
+
+    /// Returns the contact point if the two shapes overlap.
+    pub fn contact(
+        &self,
+        position: Vec3,
+        rotation: Quat,
+        other: &ColliderShape,
+        other_position: Vec3,
+        other_rotation: Quat,
+    ) -> Option<Vec3> {
+        let parts = self.parts(position, rotation);
+        let other_parts = other.parts(other_position, other_rotation);
+        parts.iter().find_map(|part| {
+            other_parts
+                .iter()
+                .find_map(|other_part| part.contact(other_part))
+        })
+    }
+
+    fn parts(&self, position: Vec3, rotation: Quat) -> Vec<Part> {
+        let to_world = |point: Vec3| position + rotation * point;
+        match self {
+            Self::Sphere { radius } => vec![Part::sphere(position, *radius)],
+            Self::Capsule { start, end, radius } => vec![Part {
+                core: Core::Segment(to_world(*start), to_world(*end)),
+                radius: *radius,
+            }],
+            Self::Cuboid {
+                center,
+                half_extents,
+            } => vec![Part {
+                core: Core::Cuboid {
+                    center: to_world(*center),
+                    rotation,
+                    half_extents: *half_extents,
+                },
+                radius: 0.0,
+            }],
+            Self::Compound(spheres) => spheres
+                .iter()
+                .map(|(offset, radius)| Part::sphere(to_world(*offset), *radius))
+                .collect(),
+        }
+    }
+}
+
+/// A convex piece of a shape in world space: a point, a segment or a box, grown by a radius.
+#[derive(Debug, Clone, Copy)]
+struct Part {
+    core: Core,
+    radius: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Core {
+    /// A point if both ends are the same.
+    Segment(Vec3, Vec3),
+    Cuboid {
+        center: Vec3,
+        rotation: Quat,
+        half_extents: Vec3,
+    },
+}
+
+impl Part {
+    fn sphere(center: Vec3, radius: f32) -> Self {
+        Self {
+            core: Core::Segment(center, center),
+            radius,
+        }
+    }
+
+    fn contact(&self, other: &Part) -> Option<Vec3> {
+        let (point, other_point) = match (self.core, other.core) {
+            (Core::Segment(start, end), Core::Segment(other_start, other_end)) => {
+                closest_points_segments(start, end, other_start, other_end)
+            }
+            (
+                Core::Segment(start, end),
+                Core::Cuboid {
+                    center,
+                    rotation,
+                    half_extents,
+                },
+            ) => closest_points_segment_cuboid(start, end, center, rotation, half_extents),
+            (
+                Core::Cuboid {
+                    center,
+                    rotation,
+                    half_extents,
+                },
+                Core::Segment(start, end),
+            ) => {
+                let (segment_point, cuboid_point) =
+                    closest_points_segment_cuboid(start, end, center, rotation, half_extents);
+                (cuboid_point, segment_point)
+            }
+            (
+                Core::Cuboid {
+                    center,
+                    rotation,
+                    half_extents,
+                },
+                Core::Cuboid {
+                    center: other_center,
+                    rotation: other_rotation,
+                    half_extents: other_half_extents,
+                },
+            ) => {
+                if !cuboids_overlap(
+                    (center, rotation, half_extents),
+                    (other_center, other_rotation, other_half_extents),
+                ) {
+                    return None;
+                }
+                // somewhere inside both boxes
+                let point = closest_point_cuboid(other_center, center, rotation, half_extents);
+                let other_point =
+                    closest_point_cuboid(center, other_center, other_rotation, other_half_extents);
+                return Some((point + other_point) / 2.0);
+            }
+        };
+
+        let distance = point.distance(other_point);
+        if distance >= self.radius + other.radius {
+            return None;
+        }
+        // the middle of the overlapping part, along the line between the closest points
+        let penetration = self.radius + other.radius - distance;
+        Some(point + (other_point - point).normalize_or_zero() * (self.radius - penetration / 2.0))
+    }
+}
+
+/// The closest points between the segments `start..end` and `other_start..other_end`.
+fn closest_points_segments(
+    start: Vec3,
+    end: Vec3,
+    other_start: Vec3,
+    other_end: Vec3,
+) -> (Vec3, Vec3) {
+    let direction = end - start;
+    let other_direction = other_end - other_start;
+    let offset = start - other_start;
+    let length_squared = direction.length_squared();
+    let other_length_squared = other_direction.length_squared();
+    let other_offset = other_direction.dot(offset);
+
+    if length_squared <= f32::EPSILON && other_length_squared <= f32::EPSILON {
+        return (start, other_start);
+    }
+
+    let (s, t) = if length_squared <= f32::EPSILON {
+        (0.0, (other_offset / other_length_squared).clamp(0.0, 1.0))
+    } else {
+        let c = direction.dot(offset);
+        if other_length_squared <= f32::EPSILON {
+            ((-c / length_squared).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = direction.dot(other_direction);
+            let denominator = length_squared * other_length_squared - b * b;
+            // parallel segments, any point will do as a start
+            let s = if denominator > f32::EPSILON {
+                ((b * other_offset - c * other_length_squared) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let t = (b * s + other_offset) / other_length_squared;
+            if t < 0.0 {
+                ((-c / length_squared).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / length_squared).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    (start + direction * s, other_start + other_direction * t)
+}
+
+fn closest_point_cuboid(point: Vec3, center: Vec3, rotation: Quat, half_extents: Vec3) -> Vec3 {
+    let local = rotation.inverse() * (point - center);
+    center + rotation * local.clamp(-half_extents, half_extents)
+}
+
+/// The closest points between a segment and a box.
+///
+/// The distance to a box along a segment is convex, so a golden section search finds the minimum.
+fn closest_points_segment_cuboid(
+    start: Vec3,
+    end: Vec3,
+    center: Vec3,
+    rotation: Quat,
+    half_extents: Vec3,
+) -> (Vec3, Vec3) {
+    let distance_at = |t: f32| {
+        let point = start.lerp(end, t);
+        point.distance_squared(closest_point_cuboid(point, center, rotation, half_extents))
+    };
+
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..SEGMENT_BOX_ITERATIONS {
+        let left = high - (high - low) * GOLDEN_RATIO;
+        let right = low + (high - low) * GOLDEN_RATIO;
+        if distance_at(left) < distance_at(right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+
+    let point = start.lerp(end, (low + high) / 2.0);
+    (
+        point,
+        closest_point_cuboid(point, center, rotation, half_extents),
+    )
+}
+
+/// Separating axis test between two oriented boxes given as `(center, rotation, half_extents)`.
+fn cuboids_overlap(a: (Vec3, Quat, Vec3), b: (Vec3, Quat, Vec3)) -> bool {
+    let axes_a = [a.1 * Vec3::X, a.1 * Vec3::Y, a.1 * Vec3::Z];
+    let axes_b = [b.1 * Vec3::X, b.1 * Vec3::Y, b.1 * Vec3::Z];
+    let offset = b.0 - a.0;
+
+    let separated_along = |axis: Vec3| {
+        // parallel edges give no axis, one of the face axes covers that case
+        if axis.length_squared() <= f32::EPSILON {
+            return false;
+        }
+        let radius_a: f32 = (0..3).map(|i| axes_a[i].dot(axis).abs() * a.2[i]).sum();
+        let radius_b: f32 = (0..3).map(|i| axes_b[i].dot(axis).abs() * b.2[i]).sum();
+        offset.dot(axis).abs() > radius_a + radius_b
+    };
+
+    let face_axes = axes_a.iter().chain(axes_b.iter()).copied();
+    let edge_axes = axes_a
+        .iter()
+        .flat_map(|axis_a| axes_b.iter().map(move |axis_b| axis_a.cross(*axis_b)));
+    !face_axes.chain(edge_axes).any(separated_along)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    fn sphere(radius: f32) -> ColliderShape {
+        ColliderShape::Sphere { radius }
+    }
+
+    // along X, centered on the origin
+    fn capsule(half_length: f32, radius: f32) -> ColliderShape {
+        ColliderShape::Capsule {
+            start: Vec3::new(-half_length, 0., 0.),
+            end: Vec3::new(half_length, 0., 0.),
+            radius,
+        }
+    }
+
+    fn cuboid(half_extents: Vec3) -> ColliderShape {
+        ColliderShape::Cuboid {
+            center: Vec3::ZERO,
+            half_extents,
+        }
+    }
+
+    fn touches(a: &ColliderShape, b: &ColliderShape, offset: Vec3) -> bool {
+        a.contact(Vec3::ZERO, Quat::IDENTITY, b, offset, Quat::IDENTITY)
+            .is_some()
+    }
+
+    #[test]
+    fn sphere_against_sphere() {
+        assert!(touches(&sphere(1.), &sphere(1.), Vec3::new(1.9, 0., 0.)));
+        assert!(!touches(&sphere(1.), &sphere(1.), Vec3::new(2.1, 0., 0.)));
+        let point = sphere(1.)
+            .contact(
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                &sphere(1.),
+                Vec3::new(1.8, 0., 0.),
+                Quat::IDENTITY,
+            )
+            .unwrap();
+        assert!(point.abs_diff_eq(Vec3::new(0.9, 0., 0.), 1e-5));
+    }
+
+    #[test]
+    fn sphere_against_capsule() {
+        // beside the middle of the segment, further than either end
+        assert!(touches(
+            &capsule(3., 1.),
+            &sphere(1.),
+            Vec3::new(0., 1.9, 0.)
+        ));
+        assert!(!touches(
+            &capsule(3., 1.),
+            &sphere(1.),
+            Vec3::new(0., 2.1, 0.)
+        ));
+        // past the end cap
+        assert!(touches(
+            &capsule(3., 1.),
+            &sphere(1.),
+            Vec3::new(4.9, 0., 0.)
+        ));
+        assert!(!touches(
+            &capsule(3., 1.),
+            &sphere(1.),
+            Vec3::new(5.1, 0., 0.)
+        ));
+    }
+
+    #[test]
+    fn sphere_against_cuboid() {
+        let cube = cuboid(Vec3::ONE);
+        assert!(touches(&cube, &sphere(1.), Vec3::new(1.9, 0., 0.)));
+        assert!(!touches(&cube, &sphere(1.), Vec3::new(2.1, 0., 0.)));
+        // off the corner the gap is wider than along the faces
+        assert!(!touches(&cube, &sphere(1.), Vec3::new(1.8, 1.8, 1.8)));
+        // inside the box
+        assert!(touches(&cube, &sphere(0.1), Vec3::ZERO));
+    }
+
+    #[test]
+    fn capsule_against_capsule() {
+        // crossing
+        let crossing = ColliderShape::Capsule {
+            start: Vec3::new(0., -3., 0.),
+            end: Vec3::new(0., 3., 0.),
+            radius: 0.5,
+        };
+        assert!(touches(
+            &capsule(3., 0.5),
+            &crossing,
+            Vec3::new(0., 0., 0.9)
+        ));
+        assert!(!touches(
+            &capsule(3., 0.5),
+            &crossing,
+            Vec3::new(0., 0., 1.1)
+        ));
+        // end to end
+        assert!(touches(
+            &capsule(3., 0.5),
+            &capsule(3., 0.5),
+            Vec3::new(6.9, 0., 0.)
+        ));
+        assert!(!touches(
+            &capsule(3., 0.5),
+            &capsule(3., 0.5),
+            Vec3::new(7.1, 0., 0.)
+        ));
+    }
+
+    #[test]
+    fn capsule_against_cuboid() {
+        let cube = cuboid(Vec3::ONE);
+        assert!(touches(&cube, &capsule(3., 0.5), Vec3::new(0., 1.4, 0.)));
+        assert!(!touches(&cube, &capsule(3., 0.5), Vec3::new(0., 1.6, 0.)));
+        // only the end of the capsule reaches the box
+        assert!(touches(&cube, &capsule(3., 0.5), Vec3::new(4.4, 0., 0.)));
+        assert!(!touches(&cube, &capsule(3., 0.5), Vec3::new(4.6, 0., 0.)));
+    }
+
+    #[test]
+    fn cuboid_against_cuboid() {
+        let cube = cuboid(Vec3::ONE);
+        assert!(touches(&cube, &cube, Vec3::new(1.9, 1.9, 0.)));
+        assert!(!touches(&cube, &cube, Vec3::new(2.1, 0., 0.)));
+
+        // turned on its edge, the corner reaches sqrt(2) out
+        let turned = Quat::from_rotation_z(FRAC_PI_4);
+        let at = |x: f32| {
+            cube.contact(
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                &cube,
+                Vec3::new(x, 0., 0.),
+                turned,
+            )
+        };
+        assert!(at(2.3).is_some());
+        assert!(at(2.5).is_none());
+    }
+
+    #[test]
+    fn compound_against_other_shapes() {
+        let dumbbell = ColliderShape::Compound(vec![
+            (Vec3::new(-3., 0., 0.), 1.),
+            (Vec3::new(3., 0., 0.), 1.),
+        ]);
+        // the gap between the spheres is empty
+        assert!(!touches(&dumbbell, &sphere(0.5), Vec3::ZERO));
+        assert!(touches(&dumbbell, &sphere(0.5), Vec3::new(4.4, 0., 0.)));
+        assert!(touches(
+            &dumbbell,
+            &cuboid(Vec3::ONE),
+            Vec3::new(-1.5, 0., 0.)
+        ));
+        assert!(touches(&dumbbell, &dumbbell, Vec3::new(0., 1.9, 0.)));
+        assert!(!touches(&dumbbell, &dumbbell, Vec3::new(0., 2.1, 0.)));
+    }
+
+    #[test]
+    fn parallel_segments() {
+        // overlapping along their length
+        let (point, other_point) = closest_points_segments(
+            Vec3::ZERO,
+            Vec3::new(10., 0., 0.),
+            Vec3::new(5., 1., 0.),
+            Vec3::new(15., 1., 0.),
+        );
+        assert!((point.distance(other_point) - 1.0).abs() < 1e-5);
+        assert!((5.0..=10.0).contains(&point.x));
+
+        // end to end
+        let (point, other_point) = closest_points_segments(
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(3., 1., 0.),
+            Vec3::new(4., 1., 0.),
+        );
+        assert!(point.abs_diff_eq(Vec3::X, 1e-5));
+        assert!(other_point.abs_diff_eq(Vec3::new(3., 1., 0.), 1e-5));
+    }
+
+    #[test]
+    fn zero_length_capsules() {
+        let point_capsule = ColliderShape::Capsule {
+            start: Vec3::ZERO,
+            end: Vec3::ZERO,
+            radius: 1.,
+        };
+        // behaves like a sphere against every shape
+        assert!(touches(&point_capsule, &sphere(1.), Vec3::new(1.9, 0., 0.)));
+        assert!(!touches(
+            &point_capsule,
+            &sphere(1.),
+            Vec3::new(2.1, 0., 0.)
+        ));
+        assert!(touches(
+            &point_capsule,
+            &point_capsule,
+            Vec3::new(0., 1.9, 0.)
+        ));
+        assert!(!touches(
+            &point_capsule,
+            &point_capsule,
+            Vec3::new(0., 2.1, 0.)
+        ));
+        assert!(touches(
+            &point_capsule,
+            &capsule(3., 0.5),
+            Vec3::new(1., 1.4, 0.)
+        ));
+        assert!(touches(
+            &point_capsule,
+            &cuboid(Vec3::ONE),
+            Vec3::new(1.9, 0., 0.)
+        ));
+        assert!(!touches(
+            &point_capsule,
+            &cuboid(Vec3::ONE),
+            Vec3::new(2.1, 0., 0.)
+        ));
+
+        let (point, other_point) =
+            closest_points_segments(Vec3::ZERO, Vec3::ZERO, Vec3::X, Vec3::new(1., 2., 0.));
+        assert_eq!(point, Vec3::ZERO);
+        assert_eq!(other_point, Vec3::X);
+    }
+}
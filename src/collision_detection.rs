@@ -1,137 +1,632 @@
-use bevy::{prelude::*, utils::HashMap};
-
-use crate::{
-    asteroids::Asteroid,
-    gamestate::Pause,
-    schedule::InGameSet,
-    spaceship::Spaceship,
-    status::Status,
+use std::ops::BitOr;
+
+use bevy::{
+    math::{Affine3A, Vec3A},
+    prelude::*,
+    render::primitives::Aabb,
+    utils::HashMap,
 };
 
-#[derive(Component, Debug)]
+use crate::{collider_shape::ColliderShape, gamestate::Pause, schedule::InGameSet};
+
+// the cell should be a bit larger than the most common collider diameter,
+// so that most colliders only touch a handful of cells
+const SPATIAL_HASH_CELL_SIZE: f32 = 10.0;
+
+/// A set of collision layers, used both for what a collider is and what it can hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CollisionLayer(pub u32);
+
+impl CollisionLayer {
+    pub const PLAYER: Self = Self(1 << 0);
+    pub const PLAYER_PROJECTILE: Self = Self(1 << 1);
+    pub const ENEMY: Self = Self(1 << 2);
+    pub const ENEMY_PROJECTILE: Self = Self(1 << 3);
+    pub const PICKUP: Self = Self(1 << 4);
+    pub const ENVIRONMENT: Self = Self(1 << 5);
+    pub const ALL: Self = Self(u32::MAX);
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for CollisionLayer {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Component, Debug, Clone)]
 pub struct Collider {
+    /// The radius of the bounding sphere, the exact shape is only used for the narrowphase.
     pub radius: f32,
-    pub colliding_entities: Vec<Entity>,
+    pub shape: ColliderShape,
+    /// The layers this collider belongs to.
+    pub membership: CollisionLayer,
+    /// The layers this collider can collide with.
+    pub filter: CollisionLayer,
 }
 
 impl Collider {
     pub fn new(radius: f32) -> Self {
+        Self::from_shape(ColliderShape::Sphere { radius })
+    }
+
+    /// Several spheres as `(offset, radius)` in the entity's local space.
+    pub fn compound(spheres: Vec<(Vec3, f32)>) -> Self {
+        Self::from_shape(ColliderShape::Compound(spheres))
+    }
+
+    pub fn from_shape(shape: ColliderShape) -> Self {
         Self {
-            radius,
-            colliding_entities: Vec::new(),
+            radius: shape.bounding_radius(),
+            shape,
+            membership: CollisionLayer::ALL,
+            filter: CollisionLayer::ALL,
         }
     }
+
+    pub fn set_shape(&mut self, shape: ColliderShape) {
+        self.radius = shape.bounding_radius();
+        self.shape = shape;
+    }
+
+    pub fn with_layers(mut self, membership: CollisionLayer, filter: CollisionLayer) -> Self {
+        self.membership = membership;
+        self.filter = filter;
+        self
+    }
+
+    /// Two colliders only interact if each one's filter accepts the other one's membership.
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.filter.intersects(other.membership) && other.filter.intersects(self.membership)
+    }
+
+    /// Returns the contact point if the two colliders overlap.
+    pub fn contact(
+        &self,
+        position: Vec3,
+        rotation: Quat,
+        other: &Collider,
+        other_position: Vec3,
+        other_rotation: Quat,
+    ) -> Option<Vec3> {
+        // cheap bounding sphere test first
+        if position.distance(other_position) >= self.radius + other.radius {
+            return None;
+        }
+        self.shape.contact(
+            position,
+            rotation,
+            &other.shape,
+            other_position,
+            other_rotation,
+        )
+    }
+}
+
+/// Replaces the collider's shape with one fitted to the bounding box of the
+/// entity's meshes, once the scene is loaded.
+#[derive(Component, Debug)]
+pub struct FitColliderToMesh;
+
+/// A mesh drawn over its entity rather than part of its model, like a shield bubble,
+/// which `FitColliderToMesh` leaves out.
+#[derive(Component, Debug)]
+pub struct ColliderOverlay;
+
+/// A touching pair of colliders, `entity_a` is always the lower of the two entities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub entity_a: Entity,
+    pub entity_b: Entity,
+    pub point: Vec3,
+}
+
+impl Contact {
+    fn new(entity_a: Entity, entity_b: Entity, point: Vec3) -> Self {
+        Self {
+            entity_a: entity_a.min(entity_b),
+            entity_b: entity_a.max(entity_b),
+            point,
+        }
+    }
+
+    /// Both orderings of the pair, handy to check "a hit b" and "b hit a" in one loop.
+    pub fn pairs(&self) -> [(Entity, Entity); 2] {
+        [
+            (self.entity_a, self.entity_b),
+            (self.entity_b, self.entity_a),
+        ]
+    }
+}
+
+/// Sent on the first frame two colliders touch.
+#[derive(Event, Debug, Clone, Copy, Deref)]
+pub struct CollisionStarted(pub Contact);
+
+/// Sent on every following frame the two colliders keep touching.
+#[derive(Event, Debug, Clone, Copy, Deref)]
+pub struct CollisionOngoing(pub Contact);
+
+/// Sent once the two colliders stop touching, or one of them is gone.
+/// The contact point is the last one seen.
+#[derive(Event, Debug, Clone, Copy, Deref)]
+pub struct CollisionEnded(pub Contact);
+
+/// The contacts found last frame, used to tell started and ended collisions apart.
+#[derive(Resource, Debug, Default)]
+pub struct ActiveCollisions {
+    contacts: HashMap<(Entity, Entity), Contact>,
+}
+
+/// Continuous collision detection for fast colliders.
+///
+/// The collider is swept from last frame's position to this frame's one, so it
+/// can't skip over a small collider in a single long frame.
+#[derive(Component, Debug, Default)]
+pub struct Ccd {
+    previous_position: Option<Vec3>,
+}
+
+impl Ccd {
+    /// Sweeps from where the entity is next time, instead of across the jump to it.
+    pub fn teleport(&mut self) {
+        self.previous_position = None;
+    }
+}
+
+/// A collider moving from `start` to `end` during the frame, both are equal
+/// for colliders without [`Ccd`].
+#[derive(Debug, Clone)]
+struct SpatialHashEntry {
+    entity: Entity,
+    start: Vec3,
+    end: Vec3,
+    rotation: Quat,
+    collider: Collider,
+}
+
+impl SpatialHashEntry {
+    fn min(&self) -> Vec3 {
+        self.start.min(self.end) - Vec3::splat(self.collider.radius)
+    }
+
+    fn max(&self) -> Vec3 {
+        self.start.max(self.end) + Vec3::splat(self.collider.radius)
+    }
+}
+
+/// Uniform grid broadphase, rebuilt every frame from the colliders.
+///
+/// Every collider is stored in each cell its bounding box overlaps, so only
+/// colliders sharing a cell need the exact shape test.
+#[derive(Resource, Debug)]
+pub struct SpatialHash {
+    pub cell_size: f32,
+    entries: Vec<SpatialHashEntry>,
+    // indices into `entries`
+    cells: HashMap<IVec3, Vec<usize>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(SPATIAL_HASH_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        // keep the allocations of the cells used last frame, they are likely reused,
+        // and drop the others so the map doesn't grow with every cell ever visited
+        self.cells.retain(|_, entries| {
+            let used = !entries.is_empty();
+            entries.clear();
+            used
+        });
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3, rotation: Quat, collider: &Collider) {
+        self.insert_swept(entity, position, position, rotation, collider);
+    }
+
+    /// Inserts a collider moving from `start` to `end`, covering every cell along the way.
+    pub fn insert_swept(
+        &mut self,
+        entity: Entity,
+        start: Vec3,
+        end: Vec3,
+        rotation: Quat,
+        collider: &Collider,
+    ) {
+        let entry = SpatialHashEntry {
+            entity,
+            start,
+            end,
+            rotation,
+            collider: collider.clone(),
+        };
+        let min = self.cell_of(entry.min());
+        let max = self.cell_of(entry.max());
+        let index = self.entries.len();
+        self.entries.push(entry);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    self.cells
+                        .entry(IVec3::new(x, y, z))
+                        .or_default()
+                        .push(index);
+                }
+            }
+        }
+    }
+
+    /// Returns every pair of overlapping colliders exactly once.
+    pub fn colliding_pairs(&self) -> Vec<Contact> {
+        let mut pairs = Vec::new();
+        for (&cell, indices) in self.cells.iter() {
+            for (i, &index_a) in indices.iter().enumerate() {
+                for &index_b in indices[i + 1..].iter() {
+                    let (a, b) = (&self.entries[index_a], &self.entries[index_b]);
+                    if !a.collider.interacts_with(&b.collider) {
+                        continue;
+                    }
+                    // a pair shares every cell where both bounding boxes overlap,
+                    // only report it from the cell holding the overlap's min corner
+                    let overlap_min = a.min().max(b.min());
+                    if self.cell_of(overlap_min) != cell {
+                        continue;
+                    }
+
+                    // test the shapes where they came the closest during the frame
+                    let t = closest_approach(a.start, a.end, b.start, b.end);
+                    if let Some(point) = a.collider.contact(
+                        a.start.lerp(a.end, t),
+                        a.rotation,
+                        &b.collider,
+                        b.start.lerp(b.end, t),
+                        b.rotation,
+                    ) {
+                        pairs.push(Contact::new(a.entity, b.entity, point));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    fn cell_of(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+}
+
+/// The time in `0..=1` at which two points moving linearly during the frame are
+/// the closest to each other.
+pub fn closest_approach(a_start: Vec3, a_end: Vec3, b_start: Vec3, b_end: Vec3) -> f32 {
+    // look at b from a's point of view, then it is a point against a segment
+    let offset = b_start - a_start;
+    let relative_motion = (b_end - a_end) - offset;
+    let length_squared = relative_motion.length_squared();
+    if length_squared <= f32::EPSILON {
+        return 0.0;
+    }
+    (-offset.dot(relative_motion) / length_squared).clamp(0.0, 1.0)
 }
 
 pub struct CollisionDetectionPlugin;
 
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            collision_detection
-                .before(InGameSet::CollisionDetection)
-                .run_if(|pause: Res<Pause>| !pause.is_paused),
-        )
-        .add_systems(
-            Update,
-            handle_collisions
-                .in_set(InGameSet::DespawnEntities)
-                .run_if(|pause: Res<Pause>| !pause.is_paused),
-        );
+        app.init_resource::<SpatialHash>()
+            .init_resource::<ActiveCollisions>()
+            .add_event::<CollisionStarted>()
+            .add_event::<CollisionOngoing>()
+            .add_event::<CollisionEnded>()
+            .add_systems(Update, fit_collider_to_mesh)
+            .add_systems(
+                FixedUpdate,
+                (update_spatial_hash, collision_detection)
+                    .chain()
+                    .after(InGameSet::EntityUpdates)
+                    .before(InGameSet::CollisionDetection)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            );
     }
 }
 
-fn collision_detection(
-    mut query: Query<(
-        Entity,
-        &GlobalTransform,
-        &mut Collider,
-    )>,
+fn update_spatial_hash(
+    mut query: Query<(Entity, &Transform, &Collider, Option<&mut Ccd>)>,
+    mut spatial_hash: ResMut<SpatialHash>,
 ) {
-    let mut colliding_entities = HashMap::new();
-
-    for (entity_a, transform_a, collider_a) in query.iter() {
-        for (entity_b, transform_b, collider_b) in query.iter() {
-            if entity_a != entity_b {
-                let distance = transform_a
-                    .translation()
-                    .distance(transform_b.translation());
-                if distance < collider_a.radius + collider_b.radius {
-                    colliding_entities
-                        .entry(entity_a)
-                        .or_insert_with(Vec::new)
-                        .push(entity_b);
-                }
+    spatial_hash.clear();
+    // colliders are root entities, so the simulated transform is the world one
+    for (entity, transform, collider, ccd) in query.iter_mut() {
+        let (rotation, position) = (transform.rotation, transform.translation);
+        match ccd {
+            Some(mut ccd) => {
+                let start = ccd.previous_position.unwrap_or(position);
+                spatial_hash.insert_swept(entity, start, position, rotation, collider);
+                ccd.previous_position = Some(position);
             }
+            None => spatial_hash.insert(entity, position, rotation, collider),
         }
     }
+}
+
+fn collision_detection(
+    spatial_hash: Res<SpatialHash>,
+    mut active_collisions: ResMut<ActiveCollisions>,
+    mut collision_started: EventWriter<CollisionStarted>,
+    mut collision_ongoing: EventWriter<CollisionOngoing>,
+    mut collision_ended: EventWriter<CollisionEnded>,
+) {
+    let mut contacts = HashMap::new();
 
-    for (entity, _, mut collider) in query.iter_mut() {
-        collider.colliding_entities.clear();
-        if let Some(collisions) = colliding_entities.get(&entity) {
-            collider
-                .colliding_entities
-                .extend(collisions.iter().copied());
+    for contact in spatial_hash.colliding_pairs() {
+        let key = (contact.entity_a, contact.entity_b);
+        if active_collisions.contacts.contains_key(&key) {
+            collision_ongoing.send(CollisionOngoing(contact));
+        } else {
+            collision_started.send(CollisionStarted(contact));
         }
+        contacts.insert(key, contact);
     }
+
+    for (key, contact) in active_collisions.contacts.drain() {
+        if !contacts.contains_key(&key) {
+            collision_ended.send(CollisionEnded(contact));
+        }
+    }
+
+    active_collisions.contacts = contacts;
 }
 
-fn handle_collisions(
+#[allow(clippy::type_complexity)]
+fn fit_collider_to_mesh(
     mut commands: Commands,
-    query: Query<
-        (Entity, &Collider, &mut Status),
-        (
-            With<Asteroid>,
-            Without<Spaceship>,
-        ),
+    mut query: Query<(Entity, &GlobalTransform, &mut Collider), With<FitColliderToMesh>>,
+    children_query: Query<&Children>,
+    mesh_query: Query<
+        (&GlobalTransform, Option<&Aabb>),
+        (With<Handle<Mesh>>, Without<ColliderOverlay>),
     >,
-    mut spaceship_query: Query<&mut Status, With<Spaceship>>,
 ) {
-    /* let mut to_reduce_health = Vec::new();
-    let mut to_despawn = Vec::new();
- */
-    for (entity, collider, _) in query.iter() {
-        for &collided_entity in collider.colliding_entities.iter() {
-            if query.get(collided_entity).is_ok() {
+    for (entity, transform, mut collider) in query.iter_mut() {
+        // the collider ignores scale, so fit the box in world units
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        let world_to_local = Affine3A::from_rotation_translation(rotation, translation).inverse();
+
+        let mut min = Vec3::MAX;
+        let mut max = Vec3::MIN;
+        let mut loaded = true;
+        for descendant in children_query.iter_descendants(entity) {
+            let Ok((mesh_transform, aabb)) = mesh_query.get(descendant) else {
                 continue;
+            };
+            // the bounds are computed a frame after the mesh is loaded
+            let Some(aabb) = aabb else {
+                loaded = false;
+                break;
+            };
+            let mesh_to_local = world_to_local * mesh_transform.affine();
+            for corner in 0..8 {
+                let sign = Vec3::new(
+                    if corner & 1 == 0 { -1.0 } else { 1.0 },
+                    if corner & 2 == 0 { -1.0 } else { 1.0 },
+                    if corner & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                let point = Vec3::from(aabb.center + aabb.half_extents * Vec3A::from(sign));
+                let point = mesh_to_local.transform_point3(point);
+                min = min.min(point);
+                max = max.max(point);
             }
-            if let Ok(spaceship_status) = spaceship_query.get_single() {
-                // If the spaceship is dead, don't handle collisions
-                if spaceship_status.health == 0 {
-                    return;
-                }
+        }
+        // no mesh yet, the scene is still loading
+        if !loaded || min.x > max.x {
+            continue;
+        }
 
-                commands.entity(entity).despawn_recursive();
-                spaceship_query.single_mut().score += 1;
-            }
-            if let Ok(mut status) = spaceship_query.get_mut(collided_entity) {
-                status.health -= 1;
-                commands.entity(entity).despawn_recursive();
+        collider.set_shape(ColliderShape::fit_aabb(min, max));
+        commands.entity(entity).remove::<FitColliderToMesh>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    /// Every pair tested against every other, what the spatial hash must match.
+    fn brute_force_pairs(entries: &[SpatialHashEntry]) -> Vec<Contact> {
+        let mut pairs = Vec::new();
+        for (i, a) in entries.iter().enumerate() {
+            for b in entries[i + 1..].iter() {
+                if !a.collider.interacts_with(&b.collider) {
+                    continue;
+                }
+                let t = closest_approach(a.start, a.end, b.start, b.end);
+                if let Some(point) = a.collider.contact(
+                    a.start.lerp(a.end, t),
+                    a.rotation,
+                    &b.collider,
+                    b.start.lerp(b.end, t),
+                    b.rotation,
+                ) {
+                    pairs.push(Contact::new(a.entity, b.entity, point));
+                }
             }
         }
+        pairs
     }
 
-    /* for entity in to_reduce_health {
-        if let Ok((_, _, mut status)) = query.get_mut(entity) {
-            status.health -= 1;
-        } else if let Ok(mut spaceship_status) = spaceship_query.get_mut(entity) {
-            spaceship_status.health -= 1;
-            info!("Your are hit! Now health: {:?}", spaceship_status.health);
-        }
+    fn sorted(mut contacts: Vec<Contact>) -> Vec<(Entity, Entity)> {
+        let mut pairs: Vec<_> = contacts
+            .drain(..)
+            .map(|contact| (contact.entity_a, contact.entity_b))
+            .collect();
+        pairs.sort();
+        pairs
     }
 
-    for entity in to_despawn {
-        if spaceship_query.get(entity).is_err() {
-            spaceship_query.single_mut().score += query.get(entity).unwrap().2.score;
-            commands.entity(entity).despawn_recursive();
+    #[test]
+    fn colliding_pairs_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut spatial_hash = SpatialHash::default();
+        for layout in 0..20 {
+            spatial_hash.clear();
+            // denser and denser layouts, some colliders spanning several cells
+            let count = 20 + layout * 10;
+            for i in 0..count {
+                let start = Vec3::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-50.0..50.0),
+                );
+                let end = if rng.gen_bool(0.2) {
+                    start + Vec3::new(rng.gen_range(-30.0..30.0), 0., rng.gen_range(-30.0..30.0))
+                } else {
+                    start
+                };
+                let mut collider = Collider::new(rng.gen_range(0.5..12.0));
+                if rng.gen_bool(0.3) {
+                    collider = collider.with_layers(CollisionLayer::PLAYER, CollisionLayer::ENEMY);
+                }
+                spatial_hash.insert_swept(
+                    Entity::from_raw(i),
+                    start,
+                    end,
+                    Quat::IDENTITY,
+                    &collider,
+                );
+            }
+
+            let pairs = sorted(spatial_hash.colliding_pairs());
+            let mut unique = pairs.clone();
+            unique.dedup();
+            assert_eq!(pairs, unique, "a pair was reported twice");
+            assert_eq!(pairs, sorted(brute_force_pairs(&spatial_hash.entries)));
         }
     }
 
-    for (entity, _, status) in query.iter() {
-        if status.health == 0 {
-            spaceship_query.single_mut().score += status.score;
-            commands.entity(entity).despawn_recursive();
+    #[test]
+    fn clear_forgets_unused_cells() {
+        let mut spatial_hash = SpatialHash::default();
+        for i in 0..100 {
+            spatial_hash.clear();
+            // roaming away, into the middle of a new cell every frame
+            spatial_hash.insert(
+                Entity::from_raw(0),
+                Vec3::new(i as f32 * 100. + 5., 5., 5.),
+                Quat::IDENTITY,
+                &Collider::new(1.),
+            );
         }
-    } */
+        assert!(spatial_hash.cells.len() <= 2);
+    }
+
+    #[test]
+    fn swept_sphere_hits_thin_target_in_one_huge_step() {
+        let mut spatial_hash = SpatialHash::default();
+        // a whole second at projectile speed, far longer than the target is thick
+        spatial_hash.insert_swept(
+            Entity::from_raw(0),
+            Vec3::new(-1000., 0., 0.),
+            Vec3::new(1000., 0., 0.),
+            Quat::IDENTITY,
+            &Collider::new(0.5),
+        );
+        spatial_hash.insert(
+            Entity::from_raw(1),
+            Vec3::new(3., 0., 0.),
+            Quat::IDENTITY,
+            &Collider::from_shape(ColliderShape::Cuboid {
+                center: Vec3::ZERO,
+                half_extents: Vec3::new(0.1, 5., 5.),
+            }),
+        );
+
+        let contacts = spatial_hash.colliding_pairs();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].entity_a, Entity::from_raw(0));
+        assert_eq!(contacts[0].entity_b, Entity::from_raw(1));
+    }
+
+    #[test]
+    fn swept_sphere_misses_target_beside_its_path() {
+        let mut spatial_hash = SpatialHash::default();
+        spatial_hash.insert_swept(
+            Entity::from_raw(0),
+            Vec3::new(-1000., 0., 0.),
+            Vec3::new(1000., 0., 0.),
+            Quat::IDENTITY,
+            &Collider::new(0.5),
+        );
+        spatial_hash.insert(
+            Entity::from_raw(1),
+            Vec3::new(3., 0., 2.),
+            Quat::IDENTITY,
+            &Collider::new(1.),
+        );
+
+        assert!(spatial_hash.colliding_pairs().is_empty());
+    }
+
+    #[test]
+    fn closest_approach_of_a_point_passing_a_still_one() {
+        let t = closest_approach(
+            Vec3::ZERO,
+            Vec3::new(10., 0., 0.),
+            Vec3::new(4., 3., 0.),
+            Vec3::new(4., 3., 0.),
+        );
+        assert!((t - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn closest_approach_clamps_to_the_frame() {
+        // moving apart, closest at the start
+        let t = closest_approach(
+            Vec3::ZERO,
+            Vec3::new(-1., 0., 0.),
+            Vec3::X,
+            Vec3::new(2., 0., 0.),
+        );
+        assert_eq!(t, 0.0);
+        // still closing in at the end
+        let t = closest_approach(
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(10., 0., 0.),
+            Vec3::new(9., 0., 0.),
+        );
+        assert_eq!(t, 1.0);
+    }
+
+    #[test]
+    fn closest_approach_parallel_motion_keeps_the_start() {
+        // same velocity, the distance never changes
+        let velocity = Vec3::new(3., 0., 4.);
+        let t = closest_approach(Vec3::ZERO, velocity, Vec3::Y, Vec3::Y + velocity);
+        assert_eq!(t, 0.0);
+    }
+
+    #[test]
+    fn closest_approach_stationary_points() {
+        let t = closest_approach(Vec3::ZERO, Vec3::ZERO, Vec3::X, Vec3::X);
+        assert_eq!(t, 0.0);
+    }
 }
@@ -0,0 +1,482 @@
+use std::{fs, path::Path};
+
+use bevy::{
+    input::{
+        gamepad::{GamepadConnection, GamepadConnectionEvent},
+        InputSystem,
+    },
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+// next to the executable's working directory, so that it's easy to find and edit
+const BINDINGS_PATH: &str = "controls.ron";
+const STICK_DEAD_ZONE: f32 = 0.15;
+// the triggers rest a little off zero on some gamepads
+const BUTTON_DEAD_ZONE: f32 = 0.1;
+
+/// What the player wants to do, whatever the key for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Forward when positive, backward when negative.
+    Thrust,
+    /// Left when positive, right when negative.
+    Turn,
+    /// Nose down when positive, up when negative, only when flying in 3D.
+    Pitch,
+    /// Around the nose, the sign matching `Transform::rotate_local_z`.
+    Roll,
+    /// Only with the inertial flight model.
+    Brake,
+    Fire,
+    Reload,
+    ToggleFireMode,
+    /// Between firing along the nose and at the mouse cursor.
+    ToggleAimMode,
+    Shield,
+    Dash,
+    /// Picks a weapon, or a ship while choosing one.
+    Slot1,
+    Slot2,
+    Slot3,
+    Slot4,
+    ToggleFlightModel,
+    ToggleFlightDimensions,
+    SwitchArenaMode,
+    Pause,
+    ToggleHud,
+    PrintDebug,
+}
+
+impl Action {
+    pub const SLOTS: [Action; 4] = [Action::Slot1, Action::Slot2, Action::Slot3, Action::Slot4];
+}
+
+/// A key driving an action, with the value it gives the action while held.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub action: Action,
+    #[serde(default = "KeyBinding::default_scale")]
+    pub scale: f32,
+}
+
+impl KeyBinding {
+    pub fn new(key: KeyCode, action: Action) -> Self {
+        Self {
+            key,
+            action,
+            scale: Self::default_scale(),
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    fn default_scale() -> f32 {
+        1.0
+    }
+}
+
+/// A mouse button driving an action.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouseButtonBinding {
+    pub button: MouseButton,
+    pub action: Action,
+}
+
+impl MouseButtonBinding {
+    pub fn new(button: MouseButton, action: Action) -> Self {
+        Self { button, action }
+    }
+}
+
+/// A gamepad button driving an action. The analog ones, like the triggers, drive it
+/// proportionally to how far they're pushed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GamepadButtonBinding {
+    pub button: GamepadButtonType,
+    pub action: Action,
+    #[serde(default = "KeyBinding::default_scale")]
+    pub scale: f32,
+}
+
+impl GamepadButtonBinding {
+    pub fn new(button: GamepadButtonType, action: Action) -> Self {
+        Self {
+            button,
+            action,
+            scale: KeyBinding::default_scale(),
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// A gamepad stick axis driving an action proportionally.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GamepadAxisBinding {
+    pub axis: GamepadAxisType,
+    pub action: Action,
+    #[serde(default = "KeyBinding::default_scale")]
+    pub scale: f32,
+}
+
+impl GamepadAxisBinding {
+    pub fn new(axis: GamepadAxisType, action: Action) -> Self {
+        Self {
+            axis,
+            action,
+            scale: KeyBinding::default_scale(),
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// Which keys, mouse buttons and gamepad inputs drive which actions, loaded from
+/// and saved to `controls.ron`. The missing fields keep their defaults, so older
+/// files still load.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub keys: Vec<KeyBinding>,
+    pub mouse_buttons: Vec<MouseButtonBinding>,
+    pub gamepad_buttons: Vec<GamepadButtonBinding>,
+    pub gamepad_axes: Vec<GamepadAxisBinding>,
+    /// The sticks are ignored below this, and scaled up to reach 1 at full tilt.
+    pub stick_dead_zone: f32,
+    /// Same as the sticks, for the analog buttons.
+    pub button_dead_zone: f32,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            keys: vec![
+                KeyBinding::new(KeyCode::W, Action::Thrust),
+                KeyBinding::new(KeyCode::S, Action::Thrust).with_scale(-1.0),
+                KeyBinding::new(KeyCode::A, Action::Turn),
+                KeyBinding::new(KeyCode::D, Action::Turn).with_scale(-1.0),
+                KeyBinding::new(KeyCode::Up, Action::Pitch),
+                KeyBinding::new(KeyCode::Down, Action::Pitch).with_scale(-1.0),
+                KeyBinding::new(KeyCode::Q, Action::Roll).with_scale(-1.0),
+                KeyBinding::new(KeyCode::E, Action::Roll),
+                KeyBinding::new(KeyCode::X, Action::Brake),
+                KeyBinding::new(KeyCode::Space, Action::Fire),
+                KeyBinding::new(KeyCode::R, Action::Reload),
+                KeyBinding::new(KeyCode::T, Action::ToggleFireMode),
+                KeyBinding::new(KeyCode::M, Action::ToggleAimMode),
+                KeyBinding::new(KeyCode::ShiftLeft, Action::Shield),
+                KeyBinding::new(KeyCode::C, Action::Dash),
+                KeyBinding::new(KeyCode::Key1, Action::Slot1),
+                KeyBinding::new(KeyCode::Key2, Action::Slot2),
+                KeyBinding::new(KeyCode::Key3, Action::Slot3),
+                KeyBinding::new(KeyCode::Key4, Action::Slot4),
+                KeyBinding::new(KeyCode::F, Action::ToggleFlightModel),
+                KeyBinding::new(KeyCode::G, Action::ToggleFlightDimensions),
+                KeyBinding::new(KeyCode::B, Action::SwitchArenaMode),
+                KeyBinding::new(KeyCode::P, Action::Pause),
+                KeyBinding::new(KeyCode::V, Action::ToggleHud),
+                KeyBinding::new(KeyCode::Return, Action::PrintDebug),
+            ],
+            mouse_buttons: vec![
+                MouseButtonBinding::new(MouseButton::Left, Action::Fire),
+                MouseButtonBinding::new(MouseButton::Right, Action::Shield),
+            ],
+            gamepad_buttons: vec![
+                GamepadButtonBinding::new(GamepadButtonType::RightTrigger2, Action::Fire),
+                GamepadButtonBinding::new(GamepadButtonType::LeftTrigger2, Action::Shield),
+                GamepadButtonBinding::new(GamepadButtonType::LeftTrigger, Action::Roll)
+                    .with_scale(-1.0),
+                GamepadButtonBinding::new(GamepadButtonType::RightTrigger, Action::Roll),
+                GamepadButtonBinding::new(GamepadButtonType::South, Action::Dash),
+                GamepadButtonBinding::new(GamepadButtonType::East, Action::Brake),
+                GamepadButtonBinding::new(GamepadButtonType::West, Action::Reload),
+                GamepadButtonBinding::new(GamepadButtonType::North, Action::ToggleFireMode),
+                GamepadButtonBinding::new(GamepadButtonType::DPadUp, Action::Slot1),
+                GamepadButtonBinding::new(GamepadButtonType::DPadRight, Action::Slot2),
+                GamepadButtonBinding::new(GamepadButtonType::DPadDown, Action::Slot3),
+                GamepadButtonBinding::new(GamepadButtonType::DPadLeft, Action::Slot4),
+                GamepadButtonBinding::new(GamepadButtonType::Start, Action::Pause),
+                GamepadButtonBinding::new(GamepadButtonType::Select, Action::ToggleHud),
+            ],
+            gamepad_axes: vec![
+                GamepadAxisBinding::new(GamepadAxisType::LeftStickY, Action::Thrust),
+                // right on the stick, right on the screen
+                GamepadAxisBinding::new(GamepadAxisType::LeftStickX, Action::Turn).with_scale(-1.0),
+                GamepadAxisBinding::new(GamepadAxisType::RightStickY, Action::Pitch),
+                GamepadAxisBinding::new(GamepadAxisType::RightStickX, Action::Roll),
+            ],
+            stick_dead_zone: STICK_DEAD_ZONE,
+            button_dead_zone: BUTTON_DEAD_ZONE,
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&text).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        fs::write(path, text).map_err(|error| error.to_string())
+    }
+}
+
+/// The actions this frame, for the systems to read instead of the keys.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    values: HashMap<Action, f32>,
+    previous: HashMap<Action, f32>,
+    /// Handled already, ignored until released.
+    consumed: HashSet<Action>,
+}
+
+impl ActionState {
+    /// Between -1 and 1, 0 when the action isn't held.
+    pub fn value(&self, action: Action) -> f32 {
+        self.values.get(&action).copied().unwrap_or(0.0)
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.value(action) != 0.0
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed(action) && self.previous.get(&action).copied().unwrap_or(0.0) == 0.0
+    }
+
+    /// Keeps the action from reading as pressed until it's released, once a system has
+    /// handled it, so that the same key doesn't trigger a second one.
+    pub fn consume(&mut self, action: Action) {
+        self.values.remove(&action);
+        self.consumed.insert(action);
+    }
+
+    /// Holds the action down since this frame, as if its key was just pressed.
+    #[cfg(test)]
+    pub fn press(&mut self, action: Action) {
+        self.values.insert(action, 1.0);
+    }
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBindings>()
+            .init_resource::<ActionState>()
+            .add_systems(PreStartup, load_bindings)
+            // before any system reads the actions in this frame
+            .add_systems(
+                PreUpdate,
+                (log_gamepad_connections, update_actions).after(InputSystem),
+            );
+    }
+}
+
+fn load_bindings(mut bindings: ResMut<InputBindings>) {
+    if !Path::new(BINDINGS_PATH).exists() {
+        // first run, write the defaults out to be edited
+        if let Err(error) = bindings.save(BINDINGS_PATH) {
+            warn!("Couldn't save the controls to {BINDINGS_PATH}: {error}");
+        }
+        return;
+    }
+    match InputBindings::load(BINDINGS_PATH) {
+        Ok(loaded) => *bindings = loaded,
+        Err(error) => {
+            warn!("Couldn't load the controls from {BINDINGS_PATH}, using the defaults: {error}")
+        }
+    }
+}
+
+/// Zero within the dead zone, then rising from 0 to 1 towards full tilt.
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone).max(f32::EPSILON)
+}
+
+fn log_gamepad_connections(mut connection_events: EventReader<GamepadConnectionEvent>) {
+    // the gamepads are read wherever they are connected, nothing else to do here
+    for event in connection_events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", event.gamepad.id, info.name)
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", event.gamepad.id)
+            }
+        }
+    }
+}
+
+fn update_actions(
+    mut actions: ResMut<ActionState>,
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    button_axes: Res<Axis<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+) {
+    let actions = &mut *actions;
+    actions.previous = std::mem::take(&mut actions.values);
+    let mut add = |action: Action, amount: f32| {
+        if amount != 0.0 {
+            let value = actions.values.entry(action).or_insert(0.0);
+            *value = (*value + amount).clamp(-1.0, 1.0);
+        }
+    };
+
+    for binding in bindings.keys.iter() {
+        if keyboard_input.pressed(binding.key) {
+            add(binding.action, binding.scale);
+        }
+    }
+    for binding in bindings.mouse_buttons.iter() {
+        if mouse_input.pressed(binding.button) {
+            add(binding.action, 1.0);
+        }
+    }
+    // every connected gamepad drives the spaceship, so any of them can be picked up
+    for gamepad in gamepads.iter() {
+        for binding in bindings.gamepad_buttons.iter() {
+            let button = GamepadButton::new(gamepad, binding.button);
+            let value = button_axes.get(button).unwrap_or(0.0);
+            add(
+                binding.action,
+                apply_dead_zone(value, bindings.button_dead_zone) * binding.scale,
+            );
+        }
+        for binding in bindings.gamepad_axes.iter() {
+            let axis = GamepadAxis::new(gamepad, binding.axis);
+            let value = axes.get(axis).unwrap_or(0.0);
+            add(
+                binding.action,
+                apply_dead_zone(value, bindings.stick_dead_zone) * binding.scale,
+            );
+        }
+    }
+    // still held since it was consumed
+    let values = &mut actions.values;
+    actions
+        .consumed
+        .retain(|action| values.remove(action).is_some());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn input_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<ActionState>();
+        world.init_resource::<InputBindings>();
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<Input<MouseButton>>();
+        world.init_resource::<Gamepads>();
+        world.init_resource::<Axis<GamepadButton>>();
+        world.init_resource::<Axis<GamepadAxis>>();
+        world
+    }
+
+    #[test]
+    fn consumed_action_waits_for_a_release() {
+        let mut world = input_world();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Key1);
+        world.run_system_once(update_actions);
+        assert!(world.resource::<ActionState>().just_pressed(Action::Slot1));
+
+        world.resource_mut::<ActionState>().consume(Action::Slot1);
+        assert!(!world.resource::<ActionState>().pressed(Action::Slot1));
+        // still held on the next frame
+        world.run_system_once(update_actions);
+        assert!(!world.resource::<ActionState>().pressed(Action::Slot1));
+
+        world
+            .resource_mut::<Input<KeyCode>>()
+            .release(KeyCode::Key1);
+        world.run_system_once(update_actions);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Key1);
+        world.run_system_once(update_actions);
+        assert!(world.resource::<ActionState>().just_pressed(Action::Slot1));
+    }
+
+    #[test]
+    fn bindings_survive_a_save_and_a_load() {
+        let path = std::env::temp_dir().join(format!("airwar-controls-{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut bindings = InputBindings::default();
+        bindings
+            .keys
+            .push(KeyBinding::new(KeyCode::K, Action::Fire));
+
+        bindings.save(path).unwrap();
+        let loaded = InputBindings::load(path);
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.unwrap(), bindings);
+    }
+
+    #[test]
+    fn missing_or_broken_bindings_are_an_error() {
+        assert!(InputBindings::load("no/such/controls.ron").is_err());
+        assert!(ron::from_str::<InputBindings>("(keys: [(key: W)])").is_err());
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        let mut world = input_world();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::W);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::S);
+        world.run_system_once(update_actions);
+        assert!(!world.resource::<ActionState>().pressed(Action::Thrust));
+
+        world.resource_mut::<Input<KeyCode>>().release(KeyCode::W);
+        world.run_system_once(update_actions);
+        assert_eq!(world.resource::<ActionState>().value(Action::Thrust), -1.0);
+    }
+
+    #[test]
+    fn dead_zone_rescales_the_rest_of_the_travel() {
+        assert_eq!(apply_dead_zone(0.1, 0.15), 0.0);
+        assert_eq!(apply_dead_zone(-0.15, 0.15), 0.0);
+        assert!((apply_dead_zone(0.575, 0.15) - 0.5).abs() < 1e-5);
+        assert!((apply_dead_zone(-1.0, 0.15) + 1.0).abs() < 1e-5);
+        // a full dead zone doesn't divide by zero
+        assert_eq!(apply_dead_zone(1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn older_files_keep_the_defaults_of_what_they_miss() {
+        let bindings: InputBindings = ron::from_str("(keys: [(key: W, action: Thrust)])").unwrap();
+        let defaults = InputBindings::default();
+
+        assert_eq!(
+            bindings.keys,
+            vec![KeyBinding::new(KeyCode::W, Action::Thrust)]
+        );
+        assert_eq!(bindings.gamepad_buttons, defaults.gamepad_buttons);
+        assert_eq!(bindings.gamepad_axes, defaults.gamepad_axes);
+        assert_eq!(bindings.stick_dead_zone, defaults.stick_dead_zone);
+        assert_eq!(bindings.button_dead_zone, defaults.button_dead_zone);
+    }
+}
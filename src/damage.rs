@@ -0,0 +1,468 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    collision_detection::{CollisionOngoing, CollisionStarted},
+    despawn::{self, DespawnQueue},
+    gamestate::Pause,
+    schedule::InGameSet,
+    shield::Shield,
+    spaceship::Spaceship,
+    status::Status,
+};
+
+// invulnerable entities are hidden this many times per second
+const BLINK_RATE: f32 = 8.0;
+
+/// How much health an entity takes away from whatever it collides with.
+#[derive(Component, Debug)]
+pub struct Damage {
+    pub amount: u32,
+}
+
+impl Damage {
+    pub fn new(amount: u32) -> Self {
+        Self { amount }
+    }
+}
+
+/// Entities only damage entities of another team.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Team {
+    Player,
+    Enemy,
+    /// Hurts both sides, e.g. the planets.
+    Neutral,
+}
+
+/// Takes no damage until the timer finishes.
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// Makes the entity `Invulnerable` for a moment after every hit it survives,
+/// so that a single contact only counts once.
+#[derive(Component, Debug)]
+pub struct HitInvulnerability {
+    pub seconds: f32,
+}
+
+impl HitInvulnerability {
+    pub fn new(seconds: f32) -> Self {
+        Self { seconds }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: u32,
+}
+
+/// A hit that isn't a collision, like a laser's. It goes through the same team,
+/// invulnerability and shield checks as the collisions before doing any damage.
+#[derive(Event, Debug)]
+pub struct HitEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: u32,
+}
+
+/// Sent once when an entity's health drops to zero.
+#[derive(Event, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub killer: Entity,
+}
+
+/// The contacts whose hit didn't land because the target was protected, by a shield or
+/// invulnerability. They are tried again for as long as they last, so that touching a
+/// planet is still lethal once the protection is gone.
+#[derive(Resource, Debug, Default)]
+struct BlockedHits {
+    pairs: HashSet<(Entity, Entity)>,
+}
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockedHits>()
+            .add_event::<HitEvent>()
+            .add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_systems(
+                FixedUpdate,
+                wear_off_invulnerability
+                    .in_set(InGameSet::EntityUpdates)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            )
+            .add_systems(
+                FixedUpdate,
+                (collision_damage, apply_damage)
+                    .chain()
+                    .in_set(InGameSet::CollisionDetection)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            )
+            .add_systems(Update, blink_invulnerable_entities)
+            .add_systems(
+                FixedUpdate,
+                despawn_dead_entities
+                    .in_set(InGameSet::DespawnEntities)
+                    .before(despawn::despawn_far_away_entities)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            );
+    }
+}
+
+/// Turns the collisions and the other hits into damage, unless they are on the same
+/// team or the target is protected.
+fn collision_damage(
+    mut collision_started: EventReader<CollisionStarted>,
+    mut collision_ongoing: EventReader<CollisionOngoing>,
+    mut hit_events: EventReader<HitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut blocked_hits: ResMut<BlockedHits>,
+    attacker_query: Query<(Option<&Damage>, &Team)>,
+    mut target_query: Query<(&Team, &Status, Has<Invulnerable>, Option<&mut Shield>)>,
+) {
+    // only kept while their contact lasts
+    let previously_blocked = std::mem::take(&mut blocked_hits.pairs);
+    let started = collision_started
+        .read()
+        .flat_map(|collision| collision.pairs());
+    let retried = collision_ongoing
+        .read()
+        .flat_map(|collision| collision.pairs())
+        .filter(|pair| previously_blocked.contains(pair));
+    // the contacts deal the attacker's `Damage`, the other hits their own amount
+    let contacts = started
+        .chain(retried)
+        .map(|(attacker, target)| (attacker, target, None));
+    let hits = hit_events
+        .read()
+        .map(|hit| (hit.source, hit.target, Some(hit.amount)));
+
+    for (attacker, target, hit_amount) in contacts.chain(hits) {
+        let Ok((damage, attacker_team)) = attacker_query.get(attacker) else {
+            continue;
+        };
+        let Some(amount) = hit_amount.or(damage.map(|damage| damage.amount)) else {
+            continue;
+        };
+        // only the contacts last, the other hits are tried again by hitting again
+        let contact = hit_amount.is_none();
+        let Ok((target_team, status, invulnerable, shield)) = target_query.get_mut(target) else {
+            continue;
+        };
+        if attacker_team == target_team {
+            continue;
+        }
+        // the hit wouldn't hurt, so it shouldn't drain the shield either
+        if invulnerable {
+            if contact {
+                blocked_hits.pairs.insert((attacker, target));
+            }
+            continue;
+        }
+        // the shield takes the hit instead, once per contact
+        if let Some(mut shield) = shield {
+            if shield.is_up() {
+                if !contact || !previously_blocked.contains(&(attacker, target)) {
+                    shield.absorb(amount, status);
+                }
+                if contact {
+                    blocked_hits.pairs.insert((attacker, target));
+                }
+                continue;
+            }
+        }
+        damage_events.send(DamageEvent {
+            target,
+            source: attacker,
+            amount,
+        });
+    }
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut blocked_hits: ResMut<BlockedHits>,
+    mut query: Query<(&mut Status, Has<Invulnerable>, Option<&HitInvulnerability>)>,
+) {
+    // who killed whom this frame
+    let mut killers = HashMap::new();
+    // who became invulnerable this frame, the component is only added later
+    let mut recovering = HashSet::new();
+
+    for event in damage_events.read() {
+        // dead attackers don't hurt anyone, so a missile touching two asteroids
+        // in the same frame only hits the first one. Two entities hitting each
+        // other still trade damage, whichever event comes first.
+        let source_is_dead = query
+            .get(event.source)
+            .is_ok_and(|(status, _, _)| status.health == 0);
+        if source_is_dead && killers.get(&event.source) != Some(&event.target) {
+            continue;
+        }
+        let Ok((mut status, invulnerable, hit_invulnerability)) = query.get_mut(event.target)
+        else {
+            continue;
+        };
+        if status.health == 0 {
+            continue;
+        }
+        if invulnerable || recovering.contains(&event.target) {
+            // tried again while the contact lasts, if it was one
+            blocked_hits.pairs.insert((event.source, event.target));
+            continue;
+        }
+
+        status.health = status.health.saturating_sub(event.amount);
+        if status.health == 0 {
+            killers.insert(event.target, event.source);
+            death_events.send(DeathEvent {
+                entity: event.target,
+                killer: event.source,
+            });
+        } else if let Some(hit_invulnerability) = hit_invulnerability {
+            recovering.insert(event.target);
+            commands
+                .entity(event.target)
+                .insert(Invulnerable::new(hit_invulnerability.seconds));
+        }
+    }
+}
+
+fn wear_off_invulnerability(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable) in query.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn blink_invulnerable_entities(
+    mut query: Query<(&Invulnerable, &mut Visibility)>,
+    mut removed: RemovedComponents<Invulnerable>,
+    mut visibility_query: Query<&mut Visibility, Without<Invulnerable>>,
+) {
+    for (invulnerable, mut visibility) in query.iter_mut() {
+        let blink = (invulnerable.timer.elapsed_secs() * BLINK_RATE * 2.0) as u32;
+        *visibility = if blink % 2 == 0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for entity in removed.read() {
+        if let Ok(mut visibility) = visibility_query.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn despawn_dead_entities(
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut death_events: EventReader<DeathEvent>,
+    spaceship_query: Query<(), With<Spaceship>>,
+) {
+    for event in death_events.read() {
+        // the spaceship stays around for the game over screen
+        if spaceship_query.get(event.entity).is_ok() {
+            continue;
+        }
+        despawn_queue.push(event.entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::collision_detection::Contact;
+
+    fn damage_world() -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<BlockedHits>();
+        world.init_resource::<Events<CollisionStarted>>();
+        world.init_resource::<Events<CollisionOngoing>>();
+        world.init_resource::<Events<HitEvent>>();
+        world.init_resource::<Events<DamageEvent>>();
+        world.init_resource::<Events<DeathEvent>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((collision_damage, apply_damage).chain());
+        (world, schedule)
+    }
+
+    fn contact(entity_a: Entity, entity_b: Entity) -> Contact {
+        Contact {
+            entity_a: entity_a.min(entity_b),
+            entity_b: entity_a.max(entity_b),
+            point: Vec3::ZERO,
+        }
+    }
+
+    fn health(world: &World, entity: Entity) -> u32 {
+        world.get::<Status>(entity).unwrap().health
+    }
+
+    #[test]
+    fn planet_kills_once_the_shield_drops() {
+        let (mut world, mut schedule) = damage_world();
+        let mut shield = Shield::new(10.0, 1.0, 1.0);
+        shield.active = true;
+        let spaceship = world.spawn((Team::Player, Status::new(3, 0), shield)).id();
+        let planet = world.spawn((Damage::new(u32::MAX), Team::Neutral)).id();
+
+        world.send_event(CollisionStarted(contact(spaceship, planet)));
+        schedule.run(&mut world);
+        assert_eq!(health(&world, spaceship), 3);
+        assert_eq!(world.get::<Shield>(spaceship).unwrap().absorbed, 3);
+
+        // still touching, the shield counts the blocked hit only once
+        world.send_event(CollisionOngoing(contact(spaceship, planet)));
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Shield>(spaceship).unwrap().absorbed, 3);
+
+        world.get_mut::<Shield>(spaceship).unwrap().active = false;
+        world.send_event(CollisionOngoing(contact(spaceship, planet)));
+        schedule.run(&mut world);
+        assert_eq!(health(&world, spaceship), 0);
+    }
+
+    #[test]
+    fn contact_started_while_invulnerable_hits_once_it_wears_off() {
+        let (mut world, mut schedule) = damage_world();
+        let spaceship = world
+            .spawn((Team::Player, Status::new(3, 0), Invulnerable::new(1.0)))
+            .id();
+        let asteroid = world.spawn((Damage::new(1), Team::Enemy)).id();
+
+        world.send_event(CollisionStarted(contact(spaceship, asteroid)));
+        schedule.run(&mut world);
+        assert_eq!(health(&world, spaceship), 3);
+
+        world.entity_mut(spaceship).remove::<Invulnerable>();
+        world.send_event(CollisionOngoing(contact(spaceship, asteroid)));
+        schedule.run(&mut world);
+        assert_eq!(health(&world, spaceship), 2);
+    }
+
+    #[test]
+    fn ongoing_contact_that_already_hit_does_nothing() {
+        let (mut world, mut schedule) = damage_world();
+        let spaceship = world.spawn((Team::Player, Status::new(3, 0))).id();
+        let asteroid = world.spawn((Damage::new(1), Team::Enemy)).id();
+
+        world.send_event(CollisionStarted(contact(spaceship, asteroid)));
+        schedule.run(&mut world);
+        world.send_event(CollisionOngoing(contact(spaceship, asteroid)));
+        schedule.run(&mut world);
+
+        assert_eq!(health(&world, spaceship), 2);
+    }
+
+    #[test]
+    fn other_hits_follow_the_same_rules() {
+        let (mut world, mut schedule) = damage_world();
+        let mut shield = Shield::new(10.0, 1.0, 1.0);
+        shield.active = true;
+        let shielded = world.spawn((Team::Enemy, Status::new(3, 0), shield)).id();
+        let ally = world.spawn((Team::Player, Status::new(3, 0))).id();
+        let target = world.spawn((Team::Enemy, Status::new(3, 0))).id();
+        let spaceship = world.spawn(Team::Player).id();
+
+        for target in [shielded, ally, target] {
+            world.send_event(HitEvent {
+                target,
+                source: spaceship,
+                amount: 2,
+            });
+        }
+        schedule.run(&mut world);
+
+        assert_eq!(health(&world, shielded), 3);
+        assert_eq!(world.get::<Shield>(shielded).unwrap().absorbed, 2);
+        assert_eq!(health(&world, ally), 3);
+        assert_eq!(health(&world, target), 1);
+    }
+
+    #[test]
+    fn neutral_hurts_both_teams_and_teammates_are_spared() {
+        let (mut world, mut schedule) = damage_world();
+        let spaceship = world
+            .spawn((Damage::new(1), Team::Player, Status::new(3, 0)))
+            .id();
+        let asteroid = world
+            .spawn((Damage::new(1), Team::Enemy, Status::new(3, 0)))
+            .id();
+        let other_asteroid = world
+            .spawn((Damage::new(1), Team::Enemy, Status::new(3, 0)))
+            .id();
+        let planet = world.spawn((Damage::new(u32::MAX), Team::Neutral)).id();
+
+        world.send_event(CollisionStarted(contact(spaceship, planet)));
+        world.send_event(CollisionStarted(contact(asteroid, planet)));
+        world.send_event(CollisionStarted(contact(asteroid, other_asteroid)));
+        schedule.run(&mut world);
+
+        assert_eq!(health(&world, spaceship), 0);
+        assert_eq!(health(&world, asteroid), 0);
+        assert_eq!(health(&world, other_asteroid), 3);
+    }
+
+    #[test]
+    fn a_survived_hit_protects_from_the_rest_of_the_tick() {
+        let (mut world, mut schedule) = damage_world();
+        let spaceship = world
+            .spawn((
+                Team::Player,
+                Status::new(3, 0),
+                HitInvulnerability::new(1.0),
+            ))
+            .id();
+        let asteroid = world.spawn((Damage::new(1), Team::Enemy)).id();
+        let other_asteroid = world.spawn((Damage::new(1), Team::Enemy)).id();
+
+        world.send_event(CollisionStarted(contact(spaceship, asteroid)));
+        world.send_event(CollisionStarted(contact(spaceship, other_asteroid)));
+        schedule.run(&mut world);
+
+        assert_eq!(health(&world, spaceship), 2);
+        assert!(world.get::<Invulnerable>(spaceship).is_some());
+    }
+
+    #[test]
+    fn invulnerability_wears_off() {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(std::time::Duration::from_secs_f32(0.6));
+        world.insert_resource(time);
+        let spaceship = world.spawn(Invulnerable::new(1.0)).id();
+
+        world.run_system_once(wear_off_invulnerability);
+        assert!(world.get::<Invulnerable>(spaceship).is_some());
+        world.run_system_once(wear_off_invulnerability);
+        assert!(world.get::<Invulnerable>(spaceship).is_none());
+    }
+}
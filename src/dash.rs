@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::{
+    despawn::Lifetime,
+    gamestate::Pause,
+    movement::{self, MaxSpeed, Velocity},
+    schedule::InGameSet,
+};
+
+const TRAIL_RADIUS: f32 = 0.8;
+const TRAIL_COLOR: Color = Color::rgb(0.4, 0.8, 1.0);
+const TRAIL_LIFETIME_SECONDS: f32 = 0.3;
+// one trail segment this often while dashing, whatever the tick rate
+const TRAIL_INTERVAL_SECONDS: f32 = 0.02;
+
+/// A short burst of speed in a fixed direction, whatever the controls say.
+#[derive(Component, Debug)]
+pub struct Dash {
+    pub speed: f32,
+    /// Runs while dashing.
+    pub duration: Timer,
+    /// Runs until the next dash.
+    pub cooldown: Timer,
+    direction: Vec3,
+    trail: Timer,
+}
+
+impl Dash {
+    pub fn new(speed: f32, duration_seconds: f32, cooldown_seconds: f32) -> Self {
+        let mut duration = Timer::from_seconds(duration_seconds, TimerMode::Once);
+        let mut cooldown = Timer::from_seconds(cooldown_seconds, TimerMode::Once);
+        // ready to go right away
+        duration.tick(duration.duration());
+        cooldown.tick(cooldown.duration());
+        Self {
+            speed,
+            duration,
+            cooldown,
+            direction: Vec3::ZERO,
+            trail: Timer::from_seconds(TRAIL_INTERVAL_SECONDS, TimerMode::Repeating),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        !self.duration.finished()
+    }
+
+    /// Starts dashing in `direction` if the cooldown is over, returns whether it did.
+    pub fn start(&mut self, direction: Vec3) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+        self.direction = direction.normalize_or_zero();
+        self.duration.reset();
+        self.cooldown.reset();
+        self.trail.reset();
+        true
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct TrailAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub struct DashPlugin;
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrailAssets>()
+            .add_systems(Startup, load_trail_assets)
+            .add_systems(
+                FixedUpdate,
+                update_dashes
+                    .in_set(InGameSet::EntityUpdates)
+                    .before(movement::update_velocity)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            );
+    }
+}
+
+fn load_trail_assets(
+    mut trail_assets: ResMut<TrailAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    *trail_assets = TrailAssets {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: TRAIL_RADIUS,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: TRAIL_COLOR,
+            unlit: true,
+            ..default()
+        }),
+    };
+}
+
+/// Keeps the dashing entities at full speed, overriding the controls, and leaves a trail.
+fn update_dashes(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Dash, &mut Velocity, Option<&mut MaxSpeed>)>,
+    trail_assets: Res<TrailAssets>,
+    time: Res<Time>,
+) {
+    for (transform, mut dash, mut velocity, max_speed) in query.iter_mut() {
+        dash.cooldown.tick(time.delta());
+        if !dash.is_dashing() {
+            continue;
+        }
+        dash.duration.tick(time.delta());
+
+        velocity.value = dash.direction * dash.speed;
+        if let Some(mut max_speed) = max_speed {
+            max_speed.value = max_speed.value.max(dash.speed);
+        }
+
+        if !dash.trail.tick(time.delta()).just_finished() {
+            continue;
+        }
+        commands.spawn((
+            PbrBundle {
+                mesh: trail_assets.mesh.clone(),
+                material: trail_assets.material.clone(),
+                transform: Transform::from_translation(transform.translation),
+                ..default()
+            },
+            Lifetime::new(TRAIL_LIFETIME_SECONDS),
+        ));
+    }
+}
@@ -1,20 +1,30 @@
 use bevy::prelude::*;
 
-use crate::{schedule::InGameSet, spaceship::Spaceship, status::Status};
+use crate::{
+    controls::{Action, ActionState},
+    despawn::ExpiredEvent,
+    schedule::InGameSet,
+    spaceship::Spaceship,
+    status::Status,
+};
 
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, print_spaceship.after(InGameSet::EntityUpdates));
+        app.add_systems(Update, print_spaceship.after(InGameSet::EntityUpdates))
+            .add_systems(
+                FixedUpdate,
+                log_expired_entities.after(InGameSet::DespawnEntities),
+            );
     }
 }
 
 fn print_spaceship(
     query: Query<(&Transform, &Status), With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Return) {
+    if actions.just_pressed(Action::PrintDebug) {
         for (transform, status) in query.iter() {
             info!(
                 "Spaceship is at transform {:?} with status {:?}",
@@ -23,3 +33,9 @@ fn print_spaceship(
         }
     }
 }
+
+fn log_expired_entities(mut expired_events: EventReader<ExpiredEvent>) {
+    for event in expired_events.read() {
+        debug!("{:?} expired at {:?}", event.entity, event.translation);
+    }
+}
@@ -1,36 +1,222 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 
-use crate::{gamestate::Pause, schedule::InGameSet, spaceship::Spaceship};
+use crate::{
+    arena::Arena, gamestate::Pause, movement::Velocity, schedule::InGameSet, spaceship::Spaceship,
+};
 
+// how far outside the arena something can get before it is gone for good
 const DESPAWN_DISTANCE: f32 = 100.0;
 
+/// Despawns the entity once the timer finishes.
+#[derive(Component, Debug)]
+pub struct Lifetime {
+    pub timer: Timer,
+}
+
+impl Lifetime {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// Despawns the entity once it is `distance` away from `origin`, where it was spawned.
+/// Wrapping around the arena moves the origin along, so only the flight counts.
+#[derive(Component, Debug)]
+pub struct MaxRange {
+    pub origin: Vec3,
+    pub distance: f32,
+}
+
+impl MaxRange {
+    pub fn new(origin: Vec3, distance: f32) -> Self {
+        Self { origin, distance }
+    }
+}
+
+/// The entities to despawn at the end of the tick. Something can die, expire and
+/// leave the arena in the same tick, this despawns it only once.
+#[derive(Resource, Debug, Default)]
+pub struct DespawnQueue {
+    entities: HashSet<Entity>,
+}
+
+impl DespawnQueue {
+    /// Returns false if the entity was already queued.
+    pub fn push(&mut self, entity: Entity) -> bool {
+        self.entities.insert(entity)
+    }
+}
+
+/// Sent when an entity is despawned because of its `Lifetime` or `MaxRange`,
+/// for the effects of running out, like an explosion.
+#[derive(Event, Debug)]
+pub struct ExpiredEvent {
+    pub entity: Entity,
+    pub translation: Vec3,
+}
+
 pub struct DespawnPlugin;
 
 impl Plugin for DespawnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            despawn_far_away_entities
-                .in_set(InGameSet::DespawnEntities)
-                .run_if(|pause: Res<Pause>| !pause.is_paused),
-        );
+        app.add_event::<ExpiredEvent>()
+            .init_resource::<DespawnQueue>()
+            .add_systems(
+                FixedUpdate,
+                (despawn_far_away_entities, despawn_expired_entities)
+                    .chain()
+                    .in_set(InGameSet::DespawnEntities)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            )
+            .add_systems(
+                FixedUpdate,
+                despawn_queued_entities
+                    .after(InGameSet::DespawnEntities)
+                    .before(InGameSet::GameOver),
+            );
     }
 }
 
-fn despawn_far_away_entities(
-    mut commands: Commands,
-    query: Query<(Entity, &GlobalTransform), Without<Spaceship>>,
-    spaceship_query: Query<&GlobalTransform, With<Spaceship>>,
+#[allow(clippy::type_complexity)]
+pub fn despawn_far_away_entities(
+    mut despawn_queue: ResMut<DespawnQueue>,
+    // the spaceship stays around for the game over screen
+    query: Query<(Entity, &Transform), (With<Velocity>, Without<Spaceship>)>,
+    arena: Res<Arena>,
 ) {
-    if let Ok(spaceship_transform) = spaceship_query.get_single() {
-        for (entity, transform) in query.iter() {
-            let distance = transform
-                .translation()
-                .distance(spaceship_transform.translation());
-
-            if distance > DESPAWN_DISTANCE {
-                commands.entity(entity).despawn_recursive();
-            }
+    let limit = arena.half_extents + Vec3::splat(DESPAWN_DISTANCE);
+    for (entity, transform) in query.iter() {
+        if transform.translation.abs().cmpgt(limit).any() {
+            despawn_queue.push(entity);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn despawn_expired_entities(
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut expired_events: EventWriter<ExpiredEvent>,
+    mut query: Query<
+        (Entity, &Transform, Option<&mut Lifetime>, Option<&MaxRange>),
+        Or<(With<Lifetime>, With<MaxRange>)>,
+    >,
+    time: Res<Time>,
+) {
+    for (entity, transform, lifetime, max_range) in query.iter_mut() {
+        let mut expired = false;
+        if let Some(mut lifetime) = lifetime {
+            expired |= lifetime.timer.tick(time.delta()).finished();
+        }
+        if let Some(max_range) = max_range {
+            expired |= transform.translation.distance(max_range.origin) >= max_range.distance;
         }
+
+        // no expiry effects for what's already dead or gone
+        if expired && despawn_queue.push(entity) {
+            expired_events.send(ExpiredEvent {
+                entity,
+                translation: transform.translation,
+            });
+        }
+    }
+}
+
+fn despawn_queued_entities(mut commands: Commands, mut despawn_queue: ResMut<DespawnQueue>) {
+    for entity in despawn_queue.entities.drain() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn despawn_world(seconds: f32) -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<Arena>();
+        world.init_resource::<DespawnQueue>();
+        world.init_resource::<Events<ExpiredEvent>>();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(seconds));
+        world.insert_resource(time);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                despawn_far_away_entities,
+                despawn_expired_entities,
+                despawn_queued_entities,
+            )
+                .chain(),
+        );
+        (world, schedule)
+    }
+
+    fn expired(world: &World) -> Vec<Entity> {
+        let events = world.resource::<Events<ExpiredEvent>>();
+        events
+            .get_reader()
+            .read(events)
+            .map(|event| event.entity)
+            .collect()
+    }
+
+    #[test]
+    fn lifetime_runs_out() {
+        let (mut world, mut schedule) = despawn_world(0.6);
+        let entity = world.spawn((Transform::IDENTITY, Lifetime::new(1.0))).id();
+
+        schedule.run(&mut world);
+        assert!(world.get_entity(entity).is_some());
+        schedule.run(&mut world);
+        assert!(world.get_entity(entity).is_none());
+        assert_eq!(expired(&world), vec![entity]);
+    }
+
+    #[test]
+    fn max_range_is_measured_from_the_origin() {
+        let (mut world, mut schedule) = despawn_world(0.0);
+        let origin = Vec3::new(50., 0., 0.);
+        let within = world
+            .spawn((
+                Transform::from_xyz(50., 0., 149.),
+                MaxRange::new(origin, 150.),
+            ))
+            .id();
+        let beyond = world
+            .spawn((
+                Transform::from_xyz(50., 0., 150.),
+                MaxRange::new(origin, 150.),
+            ))
+            .id();
+
+        schedule.run(&mut world);
+
+        assert!(world.get_entity(within).is_some());
+        assert!(world.get_entity(beyond).is_none());
+        assert_eq!(expired(&world), vec![beyond]);
+    }
+
+    #[test]
+    fn already_queued_entities_dont_expire_again() {
+        let (mut world, mut schedule) = despawn_world(2.0);
+        // far outside the arena, past its range and out of time, all in the same tick
+        let entity = world
+            .spawn((
+                Transform::from_xyz(500., 0., 0.),
+                Velocity::new(Vec3::X),
+                Lifetime::new(1.0),
+                MaxRange::new(Vec3::ZERO, 150.),
+            ))
+            .id();
+
+        schedule.run(&mut world);
+
+        assert!(world.get_entity(entity).is_none());
+        assert!(expired(&world).is_empty());
+        assert!(world.resource::<DespawnQueue>().entities.is_empty());
     }
 }
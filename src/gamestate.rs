@@ -1,6 +1,13 @@
 use bevy::prelude::*;
 
-use crate::{schedule::InGameSet, spaceship::Spaceship, status::Status};
+use crate::{
+    controls::{Action, ActionState},
+    damage::DeathEvent,
+    schedule::InGameSet,
+    ship_selection::ShipSelection,
+    spaceship::Spaceship,
+    status::{Lives, Status},
+};
 
 #[derive(Resource, Debug)]
 pub struct Pause {
@@ -16,11 +23,16 @@ impl Plugin for PausePlugin {
     }
 }
 
-fn pause_or_resume(mut pause: ResMut<Pause>, input: Res<Input<KeyCode>>, game_over: Res<GameOver>) {
-    // Pause the game if the spaceship is dead
-    if game_over.is_game_over {
+fn pause_or_resume(
+    mut pause: ResMut<Pause>,
+    actions: Res<ActionState>,
+    game_over: Res<GameOver>,
+    ship_selection: Res<ShipSelection>,
+) {
+    // Pause the game if the spaceship is dead or not picked yet
+    if game_over.is_game_over || ship_selection.is_selecting {
         pause.is_paused = true;
-    } else if input.just_pressed(KeyCode::P) {
+    } else if actions.just_pressed(Action::Pause) {
         pause.is_paused ^= true;
     }
 }
@@ -44,7 +56,7 @@ impl Plugin for GameOverPlugin {
             was_game_over: false,
             display_text: None,
         })
-        .add_systems(Update, game_over.in_set(InGameSet::GameOver))
+        .add_systems(FixedUpdate, game_over.in_set(InGameSet::GameOver))
         .add_systems(
             Update,
             display_when_spaceship_dead
@@ -54,8 +66,17 @@ impl Plugin for GameOverPlugin {
     }
 }
 
-fn game_over(query: Query<&Status, With<Spaceship>>, mut game_over: ResMut<GameOver>) {
-    if query.get_single().is_ok() && query.get_single().unwrap().health == 0 {
+fn game_over(
+    mut death_events: EventReader<DeathEvent>,
+    query: Query<Option<&Lives>, With<Spaceship>>,
+    mut game_over: ResMut<GameOver>,
+) {
+    // the spaceship respawns until it runs out of lives
+    if death_events.read().any(|event| {
+        query
+            .get(event.entity)
+            .is_ok_and(|lives| lives.map_or(true, |lives| lives.value == 0))
+    }) {
         game_over.is_game_over = true;
     }
 }
@@ -0,0 +1,76 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::schedule::InGameSet;
+
+/// Smooths the rendered position of an entity moved in `FixedUpdate`.
+///
+/// Between two simulation ticks the `Transform` is drawn part way from the
+/// previous tick's translation to the current one. Rotation is left alone,
+/// the spin is stepped with the ticks and the steering every frame.
+#[derive(Component, Debug, Default)]
+pub struct Interpolated {
+    previous: Option<Vec3>,
+    current: Option<Vec3>,
+}
+
+impl Interpolated {
+    /// Draws the entity at its simulated translation until the next tick,
+    /// instead of sliding it across the screen from where it was.
+    pub fn teleport(&mut self) {
+        self.previous = None;
+    }
+
+    /// Like `teleport`, for a move made outside `FixedUpdate`, which the next
+    /// tick would otherwise undo by restoring the last simulated translation.
+    pub fn teleport_to(&mut self, translation: Vec3) {
+        self.current = Some(translation);
+        self.previous = None;
+    }
+}
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            restore_simulation_state.before(InGameSet::UserInput),
+        )
+        .add_systems(
+            FixedUpdate,
+            store_simulation_state.after(InGameSet::GameOver),
+        )
+        .add_systems(
+            PostUpdate,
+            interpolate_transforms.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Puts back the last simulated translation, the rendered one is only for display.
+pub fn restore_simulation_state(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        if let Some(current) = interpolated.current {
+            transform.translation = current;
+        }
+        interpolated.previous = Some(transform.translation);
+    }
+}
+
+pub fn store_simulation_state(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = Some(transform.translation);
+    }
+}
+
+pub fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &Interpolated)>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) else {
+            continue;
+        };
+        transform.translation = previous.lerp(current, fixed_time.overstep_percentage());
+    }
+}
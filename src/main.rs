@@ -1,18 +1,33 @@
 // uncomment this to hide the console window
 #![windows_subsystem = "windows"]
 
+mod arena;
 mod asset_loader;
 mod asteroids;
+#[cfg(feature = "benchmark")]
+mod benchmark;
 mod camera;
+mod collider_shape;
 mod collision_detection;
+mod controls;
+mod damage;
+mod dash;
 mod debug;
 mod despawn;
 mod gamestate;
+mod interpolation;
 mod movement;
+mod physics;
+mod planets;
 mod schedule;
+mod shield;
+mod ship_selection;
 mod spaceship;
 mod status;
+mod turret;
+mod weapon;
 
+use arena::ArenaPlugin;
 use asset_loader::AssetLoaderPlugin;
 use asteroids::AsteroidsPlugin;
 use bevy::prelude::*;
@@ -20,17 +35,27 @@ use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use debug::DebugPlugin;
 use movement::MovementPlugin;
+use physics::PhysicsPlugin;
+use planets::PlanetsPlugin;
 use spaceship::SpaceshipPlugin;
 // use camera::CameraPlugin;
 use collision_detection::CollisionDetectionPlugin;
+use controls::ControlsPlugin;
+use damage::DamagePlugin;
+use dash::DashPlugin;
 use despawn::DespawnPlugin;
 use gamestate::{GameOverPlugin, PausePlugin};
+use interpolation::InterpolationPlugin;
 use schedule::SchedulePlugin;
+use shield::ShieldPlugin;
+use ship_selection::ShipSelectionPlugin;
 use status::StatusPlugin;
+use turret::TurretPlugin;
+use weapon::WeaponPlugin;
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgba_u8(27, 21, 45, 235)))
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::rgba_u8(27, 21, 45, 235)))
         .insert_resource(AmbientLight {
             color: Color::default(),
             brightness: 0.80,
@@ -47,27 +72,54 @@ fn main() {
         // User configured plugins
         // load the assets like the spaceship and asteroids, etc.
         .add_plugins(AssetLoaderPlugin)
+        // turn the keys and gamepads into actions, with the bindings from 'controls.ron'
+        .add_plugins(ControlsPlugin)
         // handle the movement of the moving objects
         .add_plugins(MovementPlugin)
+        // smooth the movement between the fixed simulation ticks
+        .add_plugins(InterpolationPlugin)
         // spawn the spaceship(player) and a camera to follow it
         .add_plugins(SpaceshipPlugin)
+        // pick a ship with the keys '1' to '4' before the game starts
+        .add_plugins(ShipSelectionPlugin)
+        // drain and recharge the shields, and draw them
+        .add_plugins(ShieldPlugin)
+        // fire the weapons and reload them
+        .add_plugins(WeaponPlugin)
+        // turn the turrets towards what they aim at
+        .add_plugins(TurretPlugin)
+        // keep the dashing entities going and draw their trail
+        .add_plugins(DashPlugin)
         // spawn the asteroids
         .add_plugins(AsteroidsPlugin)
+        // spawn the planets and pull everything towards them
+        .add_plugins(PlanetsPlugin)
         // handle the collision detection
         .add_plugins(CollisionDetectionPlugin)
+        // apply the collision damage and handle the deaths
+        .add_plugins(DamagePlugin)
+        // bounce the rigid bodies off each other
+        .add_plugins(PhysicsPlugin)
         // despawn the entities when collision happens
         .add_plugins(DespawnPlugin)
+        // keep the moving objects within the play area
+        .add_plugins(ArenaPlugin)
         // add a child camera to the spaceship
         // deprecated
         //.add_plugins(CameraPlugin)
         // show the info, press the key 'Enter' to print the spaceship info
         .add_plugins(DebugPlugin)
         // in game set
-        .add_plugins(SchedulePlugin)
+        .add_plugins(SchedulePlugin::default())
         // show the status, including score, health, etc.
         // pressed the key 'V' to show the status
         .add_plugins(StatusPlugin)
         // press the 'P' key to pause the game
-        .add_plugins((PausePlugin, GameOverPlugin))
-        .run();
+        .add_plugins((PausePlugin, GameOverPlugin));
+
+    // spawn thousands of colliders to measure the collision broadphase
+    #[cfg(feature = "benchmark")]
+    app.add_plugins(benchmark::BenchmarkPlugin);
+
+    app.run();
 }
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use crate::{collision_detection::Collider, gamestate::Pause, schedule::InGameSet, status::Status};
+use crate::{
+    collision_detection::Collider, gamestate::Pause, interpolation::Interpolated,
+    schedule::InGameSet, status::Status,
+};
 
 #[derive(Component, Debug)]
 pub struct Velocity {
@@ -24,13 +27,58 @@ impl Acceleration {
     }
 }
 
+/// The pull of the gravity wells, added on top of the `Acceleration`, which the controls
+/// are free to overwrite.
+#[derive(Component, Debug, Default)]
+pub struct Gravity {
+    pub value: Vec3,
+}
+
+/// Rotation speed in radians per second around each world axis.
+#[derive(Component, Debug)]
+pub struct AngularVelocity {
+    pub value: Vec3,
+}
+
+impl AngularVelocity {
+    pub fn new(value: Vec3) -> Self {
+        Self { value }
+    }
+}
+
+/// Fraction of the velocity (and angular velocity) lost per second.
+#[derive(Component, Debug, Default)]
+pub struct Drag {
+    pub linear: f32,
+    pub angular: f32,
+}
+
+impl Drag {
+    pub fn new(linear: f32, angular: f32) -> Self {
+        Self { linear, angular }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct MaxSpeed {
+    pub value: f32,
+}
+
+impl MaxSpeed {
+    pub fn new(value: f32) -> Self {
+        Self { value }
+    }
+}
+
 #[derive(Bundle)]
 pub struct MovingObjectBundle {
     pub velocity: Velocity,
     pub acceleration: Acceleration,
+    pub gravity: Gravity,
     pub collider: Collider,
     pub status: Status,
     pub model: SceneBundle,
+    pub interpolated: Interpolated,
 }
 
 pub struct MovementPlugin;
@@ -38,8 +86,8 @@ pub struct MovementPlugin;
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (update_velocity, update_position)
+            FixedUpdate,
+            (update_velocity, update_rotation, update_position)
                 .chain()
                 .in_set(InGameSet::EntityUpdates)
                 .run_if(|pause: Res<Pause>| !pause.is_paused),
@@ -47,14 +95,44 @@ impl Plugin for MovementPlugin {
     }
 }
 
-fn update_velocity(mut query: Query<(&Acceleration, &mut Velocity)>, time: Res<Time>) {
-    for (acceleration, mut velocity) in query.iter_mut() {
-        velocity.value += acceleration.value * time.delta_seconds();
+#[allow(clippy::type_complexity)]
+pub fn update_velocity(
+    mut query: Query<(
+        &Acceleration,
+        &mut Velocity,
+        Option<&Gravity>,
+        Option<&Drag>,
+        Option<&MaxSpeed>,
+    )>,
+    time: Res<Time>,
+) {
+    for (acceleration, mut velocity, gravity, drag, max_speed) in query.iter_mut() {
+        let gravity = gravity.map_or(Vec3::ZERO, |gravity| gravity.value);
+        velocity.value += (acceleration.value + gravity) * time.delta_seconds();
+        if let Some(drag) = drag {
+            velocity.value *= (-drag.linear * time.delta_seconds()).exp();
+        }
+        if let Some(max_speed) = max_speed {
+            velocity.value = velocity.value.clamp_length_max(max_speed.value);
+        }
     }
 }
 
-fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
+pub fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
     for (velocity, mut transform) in query.iter_mut() {
         transform.translation += velocity.value * time.delta_seconds();
     }
 }
+
+fn update_rotation(
+    mut query: Query<(&mut AngularVelocity, &mut Transform, Option<&Drag>)>,
+    time: Res<Time>,
+) {
+    for (mut angular_velocity, mut transform, drag) in query.iter_mut() {
+        if let Some(drag) = drag {
+            angular_velocity.value *= (-drag.angular * time.delta_seconds()).exp();
+        }
+        transform.rotation = Quat::from_scaled_axis(angular_velocity.value * time.delta_seconds())
+            * transform.rotation;
+    }
+}
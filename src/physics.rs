@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision_detection::{Collider, CollisionOngoing, CollisionStarted, Contact},
+    gamestate::Pause,
+    movement::Velocity,
+    schedule::InGameSet,
+};
+
+const DEFAULT_RESTITUTION: f32 = 0.8;
+
+/// Makes an entity bounce off other rigid bodies instead of passing through them.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct RigidBody {
+    /// `f32::INFINITY` for bodies that never get pushed around. A mass that
+    /// isn't positive is treated the same, as immovable.
+    pub mass: f32,
+    /// 1.0 keeps all the energy of a bounce, 0.0 keeps none of it.
+    pub restitution: f32,
+}
+
+impl RigidBody {
+    pub fn new(mass: f32) -> Self {
+        Self {
+            mass,
+            restitution: DEFAULT_RESTITUTION,
+        }
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// 0.0 for the immovable bodies, infinite or without a positive mass.
+    fn inverse_mass(&self) -> f32 {
+        if self.mass.is_finite() && self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The state of one side of a contact, copied out of the ECS so that the
+/// response only depends on its inputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SphereBody {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub radius: f32,
+    pub body: RigidBody,
+}
+
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            collision_response
+                .in_set(InGameSet::CollisionDetection)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        );
+    }
+}
+
+fn collision_response(
+    mut collision_started: EventReader<CollisionStarted>,
+    mut collision_ongoing: EventReader<CollisionOngoing>,
+    mut query: Query<(&RigidBody, &Collider, &mut Transform, &mut Velocity)>,
+) {
+    let mut contacts: Vec<Contact> = collision_started
+        .read()
+        .map(|event| event.0)
+        .chain(collision_ongoing.read().map(|event| event.0))
+        .collect();
+    // resolve in a fixed order, the events come out of a hash map
+    contacts.sort_by_key(|contact| (contact.entity_a, contact.entity_b));
+
+    for contact in contacts {
+        let Ok([a, b]) = query.get_many_mut([contact.entity_a, contact.entity_b]) else {
+            continue;
+        };
+        let (body_a, collider_a, mut transform_a, mut velocity_a) = a;
+        let (body_b, collider_b, mut transform_b, mut velocity_b) = b;
+
+        let mut sphere_a = SphereBody {
+            position: transform_a.translation,
+            velocity: velocity_a.value,
+            radius: collider_a.radius,
+            body: *body_a,
+        };
+        let mut sphere_b = SphereBody {
+            position: transform_b.translation,
+            velocity: velocity_b.value,
+            radius: collider_b.radius,
+            body: *body_b,
+        };
+        resolve_contact(&mut sphere_a, &mut sphere_b);
+
+        transform_a.translation = sphere_a.position;
+        velocity_a.value = sphere_a.velocity;
+        transform_b.translation = sphere_b.position;
+        velocity_b.value = sphere_b.velocity;
+    }
+}
+
+/// Separates two overlapping spheres and exchanges their momentum along the contact normal.
+///
+/// Does nothing if the spheres don't overlap or both have infinite mass.
+pub fn resolve_contact(a: &mut SphereBody, b: &mut SphereBody) {
+    let offset = b.position - a.position;
+    let distance = offset.length();
+    let penetration = a.radius + b.radius - distance;
+    if penetration <= 0.0 {
+        return;
+    }
+
+    let inverse_mass_a = a.body.inverse_mass();
+    let inverse_mass_b = b.body.inverse_mass();
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+    if inverse_mass_sum == 0.0 {
+        return;
+    }
+
+    // perfectly stacked spheres have no direction to separate in, pick one
+    let normal = if distance > f32::EPSILON {
+        offset / distance
+    } else {
+        Vec3::X
+    };
+
+    // push the bodies apart, the lighter one moves further
+    a.position -= normal * penetration * inverse_mass_a / inverse_mass_sum;
+    b.position += normal * penetration * inverse_mass_b / inverse_mass_sum;
+
+    // only bounce if they are moving towards each other
+    let approaching_speed = (a.velocity - b.velocity).dot(normal);
+    if approaching_speed <= 0.0 {
+        return;
+    }
+
+    let restitution = (a.body.restitution + b.body.restitution) / 2.0;
+    let impulse = (1.0 + restitution) * approaching_speed / inverse_mass_sum;
+    a.velocity -= normal * impulse * inverse_mass_a;
+    b.velocity += normal * impulse * inverse_mass_b;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(position: Vec3, velocity: Vec3, body: RigidBody) -> SphereBody {
+        SphereBody {
+            position,
+            velocity,
+            radius: 1.0,
+            body,
+        }
+    }
+
+    fn momentum(a: &SphereBody, b: &SphereBody) -> Vec3 {
+        a.velocity * a.body.mass + b.velocity * b.body.mass
+    }
+
+    #[test]
+    fn head_on_bounce_conserves_momentum() {
+        let mut a = sphere(Vec3::ZERO, Vec3::new(3., 0., 1.), RigidBody::new(2.0));
+        let mut b = sphere(
+            Vec3::new(1.5, 0., 0.),
+            Vec3::new(-1., 0., 0.),
+            RigidBody::new(5.0),
+        );
+        let before = momentum(&a, &b);
+
+        resolve_contact(&mut a, &mut b);
+
+        assert!(momentum(&a, &b).abs_diff_eq(before, 1e-4));
+        // pushed apart until they just touch
+        assert!((a.position.distance(b.position) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn restitution_scales_the_separating_speed() {
+        let body = RigidBody::new(1.0).with_restitution(0.5);
+        let mut a = sphere(Vec3::ZERO, Vec3::new(4., 0., 0.), body);
+        let mut b = sphere(Vec3::new(1.5, 0., 0.), Vec3::ZERO, body);
+
+        resolve_contact(&mut a, &mut b);
+
+        let separating_speed = (b.velocity - a.velocity).x;
+        assert!((separating_speed - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn separating_bodies_keep_their_velocity() {
+        let mut a = sphere(Vec3::ZERO, Vec3::new(-1., 0., 0.), RigidBody::new(1.0));
+        let mut b = sphere(
+            Vec3::new(1.5, 0., 0.),
+            Vec3::new(1., 0., 0.),
+            RigidBody::new(1.0),
+        );
+
+        resolve_contact(&mut a, &mut b);
+
+        assert_eq!(a.velocity, Vec3::new(-1., 0., 0.));
+        assert_eq!(b.velocity, Vec3::new(1., 0., 0.));
+        // still pushed out of each other
+        assert!((a.position.distance(b.position) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn apart_bodies_are_left_alone() {
+        let mut a = sphere(Vec3::ZERO, Vec3::X, RigidBody::new(1.0));
+        let mut b = sphere(Vec3::new(3., 0., 0.), -Vec3::X, RigidBody::new(1.0));
+        let (before_a, before_b) = (a, b);
+
+        resolve_contact(&mut a, &mut b);
+
+        assert_eq!((a, b), (before_a, before_b));
+    }
+
+    #[test]
+    fn stacked_bodies_separate_along_x() {
+        let mut a = sphere(Vec3::ONE, Vec3::ZERO, RigidBody::new(1.0));
+        let mut b = sphere(Vec3::ONE, Vec3::ZERO, RigidBody::new(1.0));
+
+        resolve_contact(&mut a, &mut b);
+
+        assert!(a.position.abs_diff_eq(Vec3::new(0., 1., 1.), 1e-5));
+        assert!(b.position.abs_diff_eq(Vec3::new(2., 1., 1.), 1e-5));
+        assert!(a.position.is_finite() && b.position.is_finite());
+    }
+
+    #[test]
+    fn massless_or_infinite_bodies_are_never_pushed() {
+        for mass in [0.0, f32::INFINITY] {
+            let mut wall = sphere(
+                Vec3::ZERO,
+                Vec3::ZERO,
+                RigidBody::new(mass).with_restitution(1.0),
+            );
+            let mut ball = sphere(
+                Vec3::new(1.5, 0., 0.),
+                -Vec3::X,
+                RigidBody::new(1.0).with_restitution(1.0),
+            );
+
+            resolve_contact(&mut wall, &mut ball);
+
+            assert_eq!(wall.position, Vec3::ZERO);
+            assert_eq!(wall.velocity, Vec3::ZERO);
+            assert!(ball.velocity.abs_diff_eq(Vec3::X, 1e-5));
+        }
+    }
+
+    #[test]
+    fn two_immovable_bodies_stay_put() {
+        let mut a = sphere(Vec3::ZERO, Vec3::X, RigidBody::new(0.0));
+        let mut b = sphere(
+            Vec3::new(1.5, 0., 0.),
+            -Vec3::X,
+            RigidBody::new(f32::INFINITY),
+        );
+        let (before_a, before_b) = (a, b);
+
+        resolve_contact(&mut a, &mut b);
+
+        assert_eq!((a, b), (before_a, before_b));
+    }
+}
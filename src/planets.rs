@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::SceneAssets,
+    collision_detection::{Collider, CollisionLayer},
+    damage::{Damage, Team},
+    gamestate::Pause,
+    interpolation::Interpolated,
+    movement::{self, Gravity},
+    schedule::InGameSet,
+};
+
+// the radius of the planet models at scale 1
+const PLANET_MODEL_RADIUS: f32 = 2.5;
+// touching the surface is always fatal
+const PLANET_DAMAGE: u32 = u32::MAX;
+
+/// Where the planets are placed at startup.
+const PLANETS: [PlanetDescriptor; 2] = [
+    PlanetDescriptor {
+        translation: Vec3::new(60., 0., 40.),
+        radius: 8.0,
+        gravity_strength: 800.0,
+        gravity_radius: 60.0,
+        orbit: None,
+    },
+    PlanetDescriptor {
+        translation: Vec3::new(-40., 0., 30.),
+        radius: 5.0,
+        gravity_strength: 400.0,
+        gravity_radius: 40.0,
+        orbit: Some(Orbit {
+            center: Vec3::new(-60., 0., 30.),
+            angular_speed: 0.1,
+        }),
+    },
+];
+
+struct PlanetDescriptor {
+    translation: Vec3,
+    radius: f32,
+    gravity_strength: f32,
+    gravity_radius: f32,
+    orbit: Option<Orbit>,
+}
+
+#[derive(Component, Debug)]
+pub struct Planet;
+
+/// Pulls every moving object within `radius` towards the entity.
+///
+/// The arcade flight model sets the spaceship's velocity every frame, so only
+/// the inertial one can slingshot around a planet.
+#[derive(Component, Debug)]
+pub struct GravityWell {
+    /// The acceleration at a distance of 1, it falls off with the square of the distance.
+    pub strength: f32,
+    pub radius: f32,
+}
+
+/// Slowly circles the entity around `center`, in the XZ plane.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Orbit {
+    pub center: Vec3,
+    /// In radians per second.
+    pub angular_speed: f32,
+}
+
+pub struct PlanetsPlugin;
+
+impl Plugin for PlanetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_planets).add_systems(
+            FixedUpdate,
+            (apply_gravity, update_orbits)
+                .in_set(InGameSet::EntityUpdates)
+                // pull before integrating, in the same order every tick
+                .before(movement::update_velocity)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        );
+    }
+}
+
+fn spawn_planets(mut commands: Commands, scene_assets: Res<SceneAssets>) {
+    for (i, descriptor) in PLANETS.iter().enumerate() {
+        let mut planet = commands.spawn((
+            SceneBundle {
+                scene: scene_assets.planets[i % scene_assets.planets.len()].clone(),
+                transform: Transform::from_translation(descriptor.translation)
+                    .with_scale(Vec3::splat(descriptor.radius / PLANET_MODEL_RADIUS)),
+                ..default()
+            },
+            Collider::new(descriptor.radius).with_layers(
+                CollisionLayer::ENVIRONMENT,
+                CollisionLayer::PLAYER
+                    | CollisionLayer::PLAYER_PROJECTILE
+                    | CollisionLayer::ENEMY
+                    | CollisionLayer::ENEMY_PROJECTILE
+                    | CollisionLayer::ENVIRONMENT,
+            ),
+            GravityWell {
+                strength: descriptor.gravity_strength,
+                radius: descriptor.gravity_radius,
+            },
+            Damage::new(PLANET_DAMAGE),
+            Team::Neutral,
+            Interpolated::default(),
+            Planet,
+        ));
+        if let Some(orbit) = descriptor.orbit {
+            planet.insert(orbit);
+        }
+    }
+}
+
+pub fn apply_gravity(
+    well_query: Query<(&Transform, &GravityWell, Option<&Collider>)>,
+    mut query: Query<(&Transform, &mut Gravity)>,
+) {
+    for (transform, mut gravity) in query.iter_mut() {
+        gravity.value = Vec3::ZERO;
+        for (well_transform, gravity_well, collider) in well_query.iter() {
+            let offset = well_transform.translation - transform.translation;
+            let distance = offset.length();
+            if distance > gravity_well.radius {
+                continue;
+            }
+            // don't let the pull blow up inside the planet
+            let distance = distance.max(collider.map_or(1.0, |collider| collider.radius));
+            let acceleration = gravity_well.strength / (distance * distance);
+            gravity.value += offset.normalize_or_zero() * acceleration;
+        }
+    }
+}
+
+fn update_orbits(mut query: Query<(&mut Transform, &Orbit)>, time: Res<Time>) {
+    for (mut transform, orbit) in query.iter_mut() {
+        let rotation = Quat::from_rotation_y(orbit.angular_speed * time.delta_seconds());
+        transform.translation = orbit.center + rotation * (transform.translation - orbit.center);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn gravity_at(translation: Vec3) -> Vec3 {
+        let mut world = World::new();
+        world.spawn((
+            Transform::IDENTITY,
+            GravityWell {
+                strength: 100.0,
+                radius: 50.0,
+            },
+            Collider::new(5.0),
+        ));
+        let entity = world
+            .spawn((Transform::from_translation(translation), Gravity::default()))
+            .id();
+        world.run_system_once(apply_gravity);
+        world.get::<Gravity>(entity).unwrap().value
+    }
+
+    #[test]
+    fn pulls_towards_the_well_with_the_square_of_the_distance() {
+        let near = gravity_at(Vec3::new(10., 0., 0.));
+        let far = gravity_at(Vec3::new(20., 0., 0.));
+
+        assert!(near.abs_diff_eq(Vec3::new(-1., 0., 0.), 1e-5));
+        assert!(far.abs_diff_eq(near / 4.0, 1e-5));
+    }
+
+    #[test]
+    fn no_pull_outside_the_radius() {
+        assert_eq!(gravity_at(Vec3::new(0., 0., 51.)), Vec3::ZERO);
+    }
+
+    #[test]
+    fn pull_is_capped_inside_the_planet() {
+        let surface = gravity_at(Vec3::new(5., 0., 0.));
+        let inside = gravity_at(Vec3::new(1., 0., 0.));
+
+        assert!(inside.abs_diff_eq(surface, 1e-5));
+    }
+}
@@ -1,4 +1,7 @@
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+// simulation ticks per second, independent of the frame rate
+const DEFAULT_TICK_RATE_HZ: f64 = 60.0;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 pub enum InGameSet {
@@ -9,20 +12,35 @@ pub enum InGameSet {
     GameOver,
 }
 
-pub struct SchedulePlugin;
+pub struct SchedulePlugin {
+    /// How many times per second the `FixedUpdate` simulation steps.
+    pub tick_rate_hz: f64,
+}
+
+impl Default for SchedulePlugin {
+    fn default() -> Self {
+        Self {
+            tick_rate_hz: DEFAULT_TICK_RATE_HZ,
+        }
+    }
+}
 
 impl Plugin for SchedulePlugin {
     fn build(&self, app: &mut App) {
-        app.configure_sets(
-            Update,
-            (
-                InGameSet::UserInput,
-                InGameSet::EntityUpdates,
-                InGameSet::CollisionDetection,
-                InGameSet::DespawnEntities,
-                InGameSet::GameOver,
-            )
-                .chain(),
-        );
+        // the input and cosmetic systems run every frame, the simulation in fixed ticks
+        for schedule in [Update.intern(), FixedUpdate.intern()] {
+            app.configure_sets(
+                schedule,
+                (
+                    InGameSet::UserInput,
+                    InGameSet::EntityUpdates,
+                    InGameSet::CollisionDetection,
+                    InGameSet::DespawnEntities,
+                    InGameSet::GameOver,
+                )
+                    .chain(),
+            );
+        }
+        app.insert_resource(Time::<Fixed>::from_hz(self.tick_rate_hz));
     }
 }
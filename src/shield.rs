@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision_detection::{Collider, ColliderOverlay},
+    gamestate::Pause,
+    schedule::InGameSet,
+    status::Status,
+};
+
+// how much bigger than the collider the bubble is drawn
+const BUBBLE_SCALE: f32 = 1.2;
+const BUBBLE_COLOR: Color = Color::rgba(0.3, 0.6, 1.0, 0.25);
+
+/// An energy shield, blocks the collision damage while it's up.
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub energy: f32,
+    pub max_energy: f32,
+    /// Energy used per second while the shield is up.
+    pub drain_rate: f32,
+    /// Energy regained per second while it's down.
+    pub recharge_rate: f32,
+    /// Whether the shield should be up, it only is if there's energy left.
+    pub active: bool,
+    /// Total damage blocked so far.
+    pub absorbed: u32,
+}
+
+impl Shield {
+    pub fn new(max_energy: f32, drain_rate: f32, recharge_rate: f32) -> Self {
+        Self {
+            energy: max_energy,
+            max_energy,
+            drain_rate,
+            recharge_rate,
+            active: false,
+            absorbed: 0,
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.active && self.energy > 0.0
+    }
+
+    /// Blocks the damage if the shield is up, returns whether it did.
+    pub fn absorb(&mut self, amount: u32, status: &Status) -> bool {
+        if !self.is_up() {
+            return false;
+        }
+        // count what would have been lost, not the overkill
+        self.absorbed = self.absorbed.saturating_add(amount.min(status.health));
+        true
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct ShieldBubble;
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            update_shield_energy
+                .in_set(InGameSet::EntityUpdates)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        )
+        .add_systems(
+            Update,
+            (spawn_shield_bubbles, update_shield_bubbles).chain(),
+        );
+    }
+}
+
+fn update_shield_energy(mut query: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in query.iter_mut() {
+        let change = if shield.is_up() {
+            -shield.drain_rate
+        } else {
+            shield.recharge_rate
+        };
+        shield.energy =
+            (shield.energy + change * time.delta_seconds()).clamp(0.0, shield.max_energy);
+    }
+}
+
+fn spawn_shield_bubbles(
+    mut commands: Commands,
+    query: Query<Entity, Added<Shield>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in query.iter() {
+        let bubble = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::UVSphere {
+                        radius: 1.0,
+                        ..default()
+                    })),
+                    material: materials.add(StandardMaterial {
+                        base_color: BUBBLE_COLOR,
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ShieldBubble,
+                // sized from the collider, so it mustn't be fitted to
+                ColliderOverlay,
+            ))
+            .id();
+        commands.entity(entity).add_child(bubble);
+    }
+}
+
+fn update_shield_bubbles(
+    mut bubble_query: Query<(&Parent, &mut Transform, &mut Visibility), With<ShieldBubble>>,
+    query: Query<(&Shield, &Collider)>,
+) {
+    for (parent, mut transform, mut visibility) in bubble_query.iter_mut() {
+        let Ok((shield, collider)) = query.get(parent.get()) else {
+            continue;
+        };
+        // the collider is fitted to the model once it's loaded, so keep following it
+        transform.scale = Vec3::splat(collider.radius * BUBBLE_SCALE);
+        *visibility = if shield.is_up() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::SceneAssets,
+    controls::{Action, ActionState},
+    gamestate::Pause,
+    schedule::InGameSet,
+    spaceship::{self, SHIP_ROSTER},
+};
+
+/// The game stays paused until a ship is picked from the roster.
+#[derive(Resource, Debug)]
+pub struct ShipSelection {
+    pub is_selecting: bool,
+    pub menu: Option<Entity>,
+}
+
+/// The roster shown while picking a ship.
+#[derive(Component, Debug)]
+pub struct ShipMenu;
+
+pub struct ShipSelectionPlugin;
+
+impl Plugin for ShipSelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ShipSelection {
+            is_selecting: true,
+            menu: None,
+        })
+        .add_systems(Startup, spawn_ship_menu)
+        .add_systems(
+            Update,
+            // before the weapon slots read the same keys
+            select_ship
+                .before(InGameSet::UserInput)
+                .run_if(|selection: Res<ShipSelection>| selection.is_selecting),
+        );
+    }
+}
+
+fn spawn_ship_menu(mut commands: Commands, mut selection: ResMut<ShipSelection>) {
+    let style = TextStyle {
+        font: Handle::default(),
+        font_size: 30.0,
+        color: Color::WHITE,
+    };
+    let mut sections = vec![TextSection {
+        value: "Choose your ship\n".to_string(),
+        style: TextStyle {
+            font_size: 50.0,
+            ..style.clone()
+        },
+    }];
+    for (i, profile) in SHIP_ROSTER.iter().enumerate() {
+        sections.push(TextSection {
+            value: format!(
+                "{}. {}: speed {}, health {}, {}\n",
+                i + 1,
+                profile.name,
+                profile.speed,
+                profile.starting_health,
+                profile.weapon.name
+            ),
+            style: style.clone(),
+        });
+    }
+
+    let menu = commands.spawn((
+        TextBundle {
+            text: Text {
+                sections,
+                ..default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                left: Val::Percent(25.0),
+                ..default()
+            },
+            ..default()
+        },
+        ShipMenu,
+    ));
+    selection.menu = Some(menu.id());
+}
+
+fn select_ship(
+    mut commands: Commands,
+    mut selection: ResMut<ShipSelection>,
+    mut pause: ResMut<Pause>,
+    mut actions: ResMut<ActionState>,
+    scene_assets: Res<SceneAssets>,
+) {
+    let Some((slot, profile)) = Action::SLOTS
+        .into_iter()
+        .zip(SHIP_ROSTER)
+        .find(|(slot, _)| actions.just_pressed(*slot))
+    else {
+        return;
+    };
+    // the key picked the ship, it doesn't switch the weapon too
+    actions.consume(slot);
+
+    spaceship::spawn_spaceship(&mut commands, &scene_assets, profile);
+    if let Some(menu) = selection.menu.take() {
+        commands.entity(menu).despawn_recursive();
+    }
+    selection.is_selecting = false;
+    pause.is_paused = false;
+}
@@ -1,156 +1,781 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, transform::TransformSystem, window::PrimaryWindow};
+use rand::Rng;
 
 use crate::{
+    arena::Arena,
     asset_loader::SceneAssets,
-    collision_detection::Collider,
+    collision_detection::{Ccd, Collider, CollisionLayer, FitColliderToMesh},
+    controls::{Action, ActionState},
+    damage::{Damage, DeathEvent, HitInvulnerability, Invulnerable, Team},
+    dash::Dash,
     gamestate::Pause,
-    movement::{Acceleration, MovingObjectBundle, Velocity},
+    interpolation::{self, Interpolated},
+    movement::{
+        self, Acceleration, AngularVelocity, Drag, Gravity, MaxSpeed, MovingObjectBundle, Velocity,
+    },
+    planets,
     schedule::InGameSet,
-    status::Status,
+    shield::Shield,
+    status::{Lives, Status},
+    turret::Turret,
+    weapon::{FireMode, Weapon, WeaponKind, WeaponStats},
 };
 
 const STARTING_TRANSLATION: Vec3 = Vec3::new(0., 0., -20.);
-const SPACESHIP_SPEED: f32 = 25.0;
-const SPACESHIP_ROTATION_SPEED: f32 = 2.5;
 const SPACESHIP_ROLL_SPEED: f32 = 2.5;
-const SPACESHIP_RADIUS: f32 = 5.0;
+const SPACESHIP_PITCH_SPEED: f32 = 2.5;
+const INERTIAL_THRUST: f32 = 30.0;
+const INERTIAL_TURN_ACCELERATION: f32 = 10.0;
+const INERTIAL_LINEAR_DRAG: f32 = 0.3;
+const INERTIAL_ANGULAR_DRAG: f32 = 4.0;
+const INERTIAL_MAX_SPEED: f32 = 40.0;
+const INERTIAL_BRAKE_DRAG: f32 = 3.0;
+// the arcade spaceship stops drifting towards the planets as soon as it's out of their pull
+const ARCADE_GRAVITY_DRAG: f32 = 0.5;
 const SPACESHIP_CAMERA_TRANSLATION: Vec3 = Vec3::new(0., 10., -30.);
-const SPACESHIP_STARTING_HEALTH: u32 = 3;
-const MISSILE_SPEED: f32 = 50.0;
-const MISSILE_FORWARD_SPAWN_SCALAR: f32 = 7.5;
-const MISSILE_RADIUS: f32 = 1.0;
-const MISSILE_HEALTH: u32 = 1;
+const SPACESHIP_STARTING_LIVES: u32 = 3;
+const HIT_INVULNERABILITY_SECONDS: f32 = 1.0;
+const RESPAWN_INVULNERABILITY_SECONDS: f32 = 3.0;
+// the respawn point is the one furthest from any danger among this many random ones
+const RESPAWN_CANDIDATES: usize = 16;
+// fraction of the arena the respawn points are picked from, away from the boundary
+const RESPAWN_AREA: f32 = 0.5;
+// ramming always destroys whatever the spaceship runs into
+const SPACESHIP_RAM_DAMAGE: u32 = u32::MAX;
+// picked with the number keys, in this order. Hold Space to keep firing, or
+// press 'T' to fire once per press. 'R' reloads before the magazine is empty.
+const WEAPON_ROSTER: [WeaponStats; 4] = [
+    WeaponStats {
+        name: "Missiles",
+        kind: WeaponKind::Projectiles { count: 1, fan: 0.0 },
+        fire_rate: 6.0,
+        fire_mode: FireMode::Automatic,
+        ammo_capacity: 24,
+        reload_seconds: 1.5,
+        projectile_speed: 50.0,
+        damage: 1,
+        spread: 0.03,
+    },
+    WeaponStats {
+        name: "Spread shot",
+        kind: WeaponKind::Projectiles { count: 3, fan: 0.5 },
+        fire_rate: 3.0,
+        fire_mode: FireMode::Automatic,
+        ammo_capacity: 12,
+        reload_seconds: 2.0,
+        projectile_speed: 45.0,
+        damage: 1,
+        spread: 0.0,
+    },
+    WeaponStats {
+        name: "Laser",
+        kind: WeaponKind::Laser { range: 60.0 },
+        fire_rate: 4.0,
+        fire_mode: FireMode::Automatic,
+        ammo_capacity: 20,
+        reload_seconds: 3.0,
+        projectile_speed: 0.0,
+        damage: 1,
+        spread: 0.0,
+    },
+    WeaponStats {
+        name: "Homing missiles",
+        kind: WeaponKind::Homing { turn_rate: 3.0 },
+        fire_rate: 2.0,
+        fire_mode: FireMode::SemiAutomatic,
+        ammo_capacity: 6,
+        reload_seconds: 2.5,
+        projectile_speed: 30.0,
+        damage: 2,
+        spread: 0.1,
+    },
+];
+// the ships to choose from at the start, the weapons are from the roster above
+pub const SHIP_ROSTER: [ShipProfile; 4] = [
+    ShipProfile {
+        name: "Striker",
+        model: 0,
+        speed: 25.0,
+        turn_rate: 2.5,
+        // nose, body and tail
+        collider_spheres: &[
+            (Vec3::new(0., 0., 3.), 3.),
+            (Vec3::new(0., 0., 0.), 4.),
+            (Vec3::new(0., 0., -3.), 3.),
+        ],
+        fit_collider: true,
+        starting_health: 3,
+        weapon: WEAPON_ROSTER[0],
+        turret: false,
+    },
+    ShipProfile {
+        name: "Scout",
+        model: 1,
+        speed: 35.0,
+        turn_rate: 3.5,
+        collider_spheres: &[(Vec3::new(0., 0., 1.5), 2.), (Vec3::new(0., 0., -1.5), 2.)],
+        fit_collider: false,
+        starting_health: 2,
+        weapon: WEAPON_ROSTER[1],
+        turret: false,
+    },
+    ShipProfile {
+        name: "Lancer",
+        model: 2,
+        speed: 22.0,
+        turn_rate: 2.2,
+        collider_spheres: &[
+            (Vec3::new(0., 0., 4.), 2.5),
+            (Vec3::new(0., 0., 0.), 3.5),
+            (Vec3::new(0., 0., -4.), 2.5),
+        ],
+        fit_collider: false,
+        starting_health: 3,
+        weapon: WEAPON_ROSTER[2],
+        turret: false,
+    },
+    ShipProfile {
+        name: "Warden",
+        model: 3,
+        speed: 18.0,
+        turn_rate: 1.8,
+        collider_spheres: &[(Vec3::new(0., 0., 0.), 5.)],
+        fit_collider: false,
+        starting_health: 5,
+        weapon: WEAPON_ROSTER[3],
+        // the slow one can shoot around without turning
+        turret: true,
+    },
+];
+// a full shield lasts for 2.5 seconds and takes 6 seconds to recharge
+const SHIELD_MAX_ENERGY: f32 = 100.0;
+const SHIELD_DRAIN_RATE: f32 = 40.0;
+const SHIELD_RECHARGE_RATE: f32 = 16.0;
+// press 'C' to dash, the spaceship can't be hurt for a bit longer than the dash lasts
+const DASH_SPEED: f32 = 90.0;
+const DASH_DURATION_SECONDS: f32 = 0.25;
+const DASH_COOLDOWN_SECONDS: f32 = 3.0;
+const DASH_INVULNERABILITY_SECONDS: f32 = 0.5;
+const TURRET_TURN_RATE: f32 = 4.0;
 
 #[derive(Component, Debug)]
 pub struct Spaceship;
 
 #[derive(Component, Debug)]
-pub struct SpaceshipShield;
+pub struct SpaceshipCamera;
 
-#[derive(Component, Debug)]
-pub struct SpaceshipMissile;
+/// The speed the gravity wells gave the spaceship with the arcade flight model, which
+/// sets the velocity from the controls instead of accelerating it.
+#[derive(Component, Debug, Default)]
+pub struct GravityDrift {
+    pub value: Vec3,
+}
+
+/// The stats of a ship from `SHIP_ROSTER`, kept on the spaceship.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ShipProfile {
+    pub name: &'static str,
+    /// Index into `SceneAssets::spaceships`.
+    pub model: usize,
+    /// Top speed with the arcade flight model.
+    pub speed: f32,
+    /// In radians per second, with the arcade flight model.
+    pub turn_rate: f32,
+    /// `(offset, radius)` spheres along the hull, until the collider is fitted to the model
+    /// if `fit_collider`.
+    pub collider_spheres: &'static [(Vec3, f32)],
+    pub fit_collider: bool,
+    pub starting_health: u32,
+    pub weapon: WeaponStats,
+    /// Whether the weapon is on a turret, which turns towards the cursor on its own.
+    pub turret: bool,
+}
+
+/// How the spaceship reacts to the controls, press the key 'F' to switch.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlightModel {
+    /// The spaceship moves at a fixed speed while W/S is held and stops right away.
+    #[default]
+    Arcade,
+    /// W/S fire the thrusters, the spaceship keeps drifting until drag or the
+    /// brake (key 'X') slows it down.
+    Inertial,
+}
+
+/// Where the spaceship's weapon points, press the key 'M' to switch.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AimMode {
+    /// Along the nose.
+    #[default]
+    Nose,
+    /// At the point under the mouse cursor on the gameplay plane, twin-stick style, so
+    /// the hull keeps its own heading.
+    Cursor,
+}
+
+/// Where the spaceship can go, press the key 'G' to switch.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlightDimensions {
+    /// The spaceship stays in the XZ plane, rolling is only for show.
+    #[default]
+    Planar,
+    /// The arrow keys pitch the nose down and up, and turning is relative to the
+    /// spaceship, so rolling changes where a turn takes it.
+    Full3d,
+}
+
+#[derive(Resource, Debug)]
+pub struct InertialFlightSettings {
+    pub thrust: f32,
+    pub turn_acceleration: f32,
+    /// Fraction of the speed lost per second.
+    pub linear_drag: f32,
+    /// Fraction of the turn rate lost per second.
+    pub angular_drag: f32,
+    pub max_speed: f32,
+    /// Extra linear drag while the brake is held.
+    pub brake_drag: f32,
+}
+
+impl Default for InertialFlightSettings {
+    fn default() -> Self {
+        Self {
+            thrust: INERTIAL_THRUST,
+            turn_acceleration: INERTIAL_TURN_ACCELERATION,
+            linear_drag: INERTIAL_LINEAR_DRAG,
+            angular_drag: INERTIAL_ANGULAR_DRAG,
+            max_speed: INERTIAL_MAX_SPEED,
+            brake_drag: INERTIAL_BRAKE_DRAG,
+        }
+    }
+}
 
 pub struct SpaceshipPlugin;
 
 impl Plugin for SpaceshipPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_spaceship).add_systems(
-            Update,
-            (
-                spaceship_movement_control,
-                spaceship_weapon_control,
-                spaceship_shield_control,
+        app.init_resource::<FlightModel>()
+            .init_resource::<FlightDimensions>()
+            .init_resource::<InertialFlightSettings>()
+            .init_resource::<AimMode>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (
+                    switch_flight_model,
+                    switch_flight_dimensions,
+                    switch_aim_mode,
+                    spaceship_movement_control,
+                    spaceship_aim_control,
+                    spaceship_weapon_control,
+                    spaceship_shield_control,
+                    spaceship_dash_control,
+                )
+                    .chain()
+                    .in_set(InGameSet::UserInput)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
             )
-                .chain()
-                .in_set(InGameSet::UserInput)
-                .run_if(|pause: Res<Pause>| !pause.is_paused),
-        );
+            .add_systems(
+                FixedUpdate,
+                update_gravity_drift
+                    .in_set(InGameSet::EntityUpdates)
+                    .after(planets::apply_gravity)
+                    .before(movement::update_velocity)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            )
+            .add_systems(
+                FixedUpdate,
+                respawn_spaceship
+                    .in_set(InGameSet::DespawnEntities)
+                    .run_if(|pause: Res<Pause>| !pause.is_paused),
+            )
+            // follow the interpolated spaceship, not the simulated one
+            .add_systems(
+                PostUpdate,
+                follow_spaceship
+                    .after(interpolation::interpolate_transforms)
+                    .before(TransformSystem::TransformPropagate),
+            );
     }
 }
 
-fn spawn_spaceship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
-    commands
-        .spawn((
-            MovingObjectBundle {
-                velocity: Velocity::new(Vec3::ZERO),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::new(SPACESHIP_RADIUS),
-                status: Status::new(SPACESHIP_STARTING_HEALTH, 0),
-                model: SceneBundle {
-                    scene: scene_assets.spaceship.clone(),
-                    transform: Transform::from_translation(STARTING_TRANSLATION),
-                    ..default()
-                },
-            },
-            Spaceship,
-        ))
-        .with_children(|parent| {
-            // camera
-            parent.spawn(Camera3dBundle {
-                transform: Transform::from_translation(SPACESHIP_CAMERA_TRANSLATION)
-                    .looking_at(Vec3::ZERO, Vec3::Y),
+/// Spawns the player's spaceship, once a ship has been picked.
+pub fn spawn_spaceship(commands: &mut Commands, scene_assets: &SceneAssets, profile: ShipProfile) {
+    let mut spaceship = commands.spawn((
+        MovingObjectBundle {
+            velocity: Velocity::new(Vec3::ZERO),
+            acceleration: Acceleration::new(Vec3::ZERO),
+            gravity: Gravity::default(),
+            collider: Collider::compound(profile.collider_spheres.to_vec()).with_layers(
+                CollisionLayer::PLAYER,
+                CollisionLayer::ENEMY
+                    | CollisionLayer::ENEMY_PROJECTILE
+                    | CollisionLayer::PICKUP
+                    | CollisionLayer::ENVIRONMENT,
+            ),
+            status: Status::new(profile.starting_health, 0),
+            model: SceneBundle {
+                scene: scene_assets.spaceships[profile.model].clone(),
+                transform: Transform::from_translation(STARTING_TRANSLATION),
                 ..default()
-            });
-        });
+            },
+            interpolated: Interpolated::default(),
+        },
+        AngularVelocity::new(Vec3::ZERO),
+        Drag::default(),
+        MaxSpeed::new(f32::INFINITY),
+        Damage::new(SPACESHIP_RAM_DAMAGE),
+        Team::Player,
+        Lives::new(SPACESHIP_STARTING_LIVES),
+        GravityDrift::default(),
+        HitInvulnerability::new(HIT_INVULNERABILITY_SECONDS),
+        Shield::new(SHIELD_MAX_ENERGY, SHIELD_DRAIN_RATE, SHIELD_RECHARGE_RATE),
+        Weapon::new(profile.weapon),
+        Dash::new(DASH_SPEED, DASH_DURATION_SECONDS, DASH_COOLDOWN_SECONDS),
+        profile,
+        Spaceship,
+    ));
+    if profile.fit_collider {
+        spaceship.insert(FitColliderToMesh);
+    }
+    if profile.turret {
+        spaceship.insert(Turret::new(TURRET_TURN_RATE));
+    }
 }
 
-fn spaceship_movement_control(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
-    time: Res<Time>,
+fn spawn_camera(mut commands: Commands) {
+    // the camera isn't a child of the spaceship, `follow_spaceship` moves it every frame
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_translation(
+                STARTING_TRANSLATION + SPACESHIP_CAMERA_TRANSLATION,
+            )
+            .looking_at(STARTING_TRANSLATION, Vec3::Y),
+            ..default()
+        },
+        SpaceshipCamera,
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn respawn_spaceship(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Status,
+            &ShipProfile,
+            &mut Lives,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut AngularVelocity,
+            &mut Interpolated,
+            Option<&mut Ccd>,
+            &mut GravityDrift,
+        ),
+        With<Spaceship>,
+    >,
+    danger_query: Query<(&Transform, &Team), (With<Damage>, Without<Spaceship>)>,
+    arena: Res<Arena>,
+    flight_dimensions: Res<FlightDimensions>,
 ) {
-    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
+    let Ok((
+        spaceship,
+        mut transform,
+        mut status,
+        profile,
+        mut lives,
+        mut velocity,
+        mut acceleration,
+        mut angular_velocity,
+        mut interpolated,
+        ccd,
+        mut gravity_drift,
+    )) = query.get_single_mut()
+    else {
         return;
     };
-    let mut rotation = 0.0;
-    let mut roll = 0.0;
-    let mut movement = 0.0;
+    if !death_events.read().any(|event| event.entity == spaceship) {
+        return;
+    }
+    lives.value = lives.value.saturating_sub(1);
+    // the last life is game over
+    if lives.value == 0 {
+        return;
+    }
 
-    // move forward or backward
-    if keyboard_input.pressed(KeyCode::W) {
-        movement = SPACESHIP_SPEED;
-    } else if keyboard_input.pressed(KeyCode::S) {
-        movement = -SPACESHIP_SPEED;
+    let mut rng = rand::thread_rng();
+    let y_scale = match *flight_dimensions {
+        FlightDimensions::Planar => 0.0,
+        FlightDimensions::Full3d => 1.0,
+    };
+    let candidates: Vec<Vec3> = (0..RESPAWN_CANDIDATES)
+        .map(|_| {
+            Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0) * y_scale,
+                rng.gen_range(-1.0..1.0),
+            ) * arena.half_extents
+                * RESPAWN_AREA
+        })
+        .collect();
+    let clearance = |point: Vec3| {
+        danger_query
+            .iter()
+            .filter(|(_, team)| **team != Team::Player)
+            .map(|(danger_transform, _)| danger_transform.translation.distance(point))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let safest = candidates
+        .into_iter()
+        .max_by(|a, b| clearance(*a).total_cmp(&clearance(*b)))
+        .unwrap_or(STARTING_TRANSLATION);
+
+    transform.translation = safest;
+    interpolated.teleport();
+    if let Some(mut ccd) = ccd {
+        ccd.teleport();
     }
+    status.health = profile.starting_health;
+    velocity.value = Vec3::ZERO;
+    gravity_drift.value = Vec3::ZERO;
+    acceleration.value = Vec3::ZERO;
+    angular_velocity.value = Vec3::ZERO;
+    commands
+        .entity(spaceship)
+        .insert(Invulnerable::new(RESPAWN_INVULNERABILITY_SECONDS));
+}
 
-    // rotate left or right
-    if keyboard_input.pressed(KeyCode::A) {
-        rotation = SPACESHIP_ROTATION_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::D) {
-        rotation = -SPACESHIP_ROTATION_SPEED * time.delta_seconds();
+fn switch_flight_model(
+    mut flight_model: ResMut<FlightModel>,
+    mut query: Query<(&mut Acceleration, &mut AngularVelocity), With<Spaceship>>,
+    actions: Res<ActionState>,
+) {
+    if !actions.just_pressed(Action::ToggleFlightModel) {
+        return;
     }
+    *flight_model = match *flight_model {
+        FlightModel::Arcade => FlightModel::Inertial,
+        FlightModel::Inertial => FlightModel::Arcade,
+    };
+    info!("Switched to the {:?} flight model", *flight_model);
 
-    // roll left or right
-    if keyboard_input.pressed(KeyCode::Q) {
-        roll = -SPACESHIP_ROLL_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::E) {
-        roll = SPACESHIP_ROLL_SPEED * time.delta_seconds();
+    // don't keep the thrust or the turn rate of the other model
+    if let Ok((mut acceleration, mut angular_velocity)) = query.get_single_mut() {
+        acceleration.value = Vec3::ZERO;
+        angular_velocity.value = Vec3::ZERO;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn switch_flight_dimensions(
+    mut flight_dimensions: ResMut<FlightDimensions>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut AngularVelocity,
+            &mut Interpolated,
+            Option<&mut Ccd>,
+        ),
+        With<Spaceship>,
+    >,
+    actions: Res<ActionState>,
+) {
+    if !actions.just_pressed(Action::ToggleFlightDimensions) {
+        return;
     }
+    *flight_dimensions = match *flight_dimensions {
+        FlightDimensions::Planar => FlightDimensions::Full3d,
+        FlightDimensions::Full3d => FlightDimensions::Planar,
+    };
+    info!("Switched to {:?} flight", *flight_dimensions);
+
+    let Ok((
+        mut transform,
+        mut velocity,
+        mut acceleration,
+        mut angular_velocity,
+        mut interpolated,
+        ccd,
+    )) = query.get_single_mut()
+    else {
+        return;
+    };
+    angular_velocity.value = Vec3::ZERO;
+    if *flight_dimensions == FlightDimensions::Planar {
+        // back to the plane, keeping only the heading
+        let nose = transform.rotation * Vec3::Z;
+        let heading = Vec3::new(nose.x, 0., nose.z)
+            .try_normalize()
+            .unwrap_or(Vec3::Z);
+        transform.rotation = Quat::from_rotation_arc(Vec3::Z, heading);
+        transform.translation.y = 0.;
+        // this runs between the ticks, which would put the old height back
+        interpolated.teleport_to(transform.translation);
+        velocity.value.y = 0.;
+        acceleration.value.y = 0.;
+        if let Some(mut ccd) = ccd {
+            ccd.teleport();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn spaceship_movement_control(
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut AngularVelocity,
+            &mut Drag,
+            &mut MaxSpeed,
+            &GravityDrift,
+            &ShipProfile,
+        ),
+        With<Spaceship>,
+    >,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    flight_model: Res<FlightModel>,
+    flight_dimensions: Res<FlightDimensions>,
+    settings: Res<InertialFlightSettings>,
+) {
+    let Ok((
+        mut transform,
+        mut velocity,
+        mut acceleration,
+        mut angular_velocity,
+        mut drag,
+        mut max_speed,
+        gravity_drift,
+        profile,
+    )) = query.get_single_mut()
+    else {
+        return;
+    };
+    // move forward or backward
+    let thrust = actions.value(Action::Thrust);
+    // rotate left or right
+    let turn = actions.value(Action::Turn);
+    // pitch the nose down or up, only away from the plane
+    let pitch = match *flight_dimensions {
+        FlightDimensions::Planar => 0.0,
+        FlightDimensions::Full3d => actions.value(Action::Pitch),
+    };
+    // roll left or right
+    let roll = actions.value(Action::Roll) * SPACESHIP_ROLL_SPEED * time.delta_seconds();
 
     // update transform
-    transform.rotate_y(rotation);
     transform.rotate_local_z(roll);
-    velocity.value = -transform.forward() * movement;
+    // yaw around the world up in the plane, around the spaceship's up in 3D
+    let turn_axis = match *flight_dimensions {
+        FlightDimensions::Planar => Vec3::Y,
+        FlightDimensions::Full3d => transform.up(),
+    };
+    match *flight_model {
+        FlightModel::Arcade => {
+            transform.rotate_axis(turn_axis, turn * profile.turn_rate * time.delta_seconds());
+            transform.rotate_local_x(pitch * SPACESHIP_PITCH_SPEED * time.delta_seconds());
+            velocity.value = -transform.forward() * thrust * profile.speed + gravity_drift.value;
+            *drag = Drag::default();
+            max_speed.value = f32::INFINITY;
+        }
+        FlightModel::Inertial => {
+            // the movement plugin integrates the turn rate and applies the drag
+            angular_velocity.value += (turn_axis * turn + transform.right() * pitch)
+                * settings.turn_acceleration
+                * time.delta_seconds();
+            acceleration.value = -transform.forward() * thrust * settings.thrust;
+            let brake_drag = if actions.pressed(Action::Brake) {
+                settings.brake_drag
+            } else {
+                0.0
+            };
+            *drag = Drag::new(settings.linear_drag + brake_drag, settings.angular_drag);
+            max_speed.value = settings.max_speed;
+        }
+    }
+}
+
+fn switch_aim_mode(mut aim_mode: ResMut<AimMode>, actions: Res<ActionState>) {
+    if !actions.just_pressed(Action::ToggleAimMode) {
+        return;
+    }
+    *aim_mode = match *aim_mode {
+        AimMode::Nose => AimMode::Cursor,
+        AimMode::Cursor => AimMode::Nose,
+    };
+    info!("Switched to aiming with the {:?}", *aim_mode);
+}
+
+fn spaceship_aim_control(
+    mut query: Query<(&Transform, &mut Weapon, Option<&mut Turret>), With<Spaceship>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<SpaceshipCamera>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    aim_mode: Res<AimMode>,
+) {
+    let Ok((transform, mut weapon, turret)) = query.get_single_mut() else {
+        return;
+    };
+    let target = match *aim_mode {
+        AimMode::Nose => None,
+        AimMode::Cursor => cursor_on_plane(transform.translation, &camera_query, &window_query),
+    };
+    match turret {
+        // the turret turns towards the target and aims the weapon itself
+        Some(mut turret) => turret.target = target,
+        None => {
+            weapon.aim = target.and_then(|target| (target - transform.translation).try_normalize())
+        }
+    }
+}
+
+/// The point under the cursor on the horizontal plane through `origin`, which is the
+/// gameplay plane unless flying in 3D.
+fn cursor_on_plane(
+    origin: Vec3,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<SpaceshipCamera>>,
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+) -> Option<Vec3> {
+    let window = window_query.get_single().ok()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let ray = camera.viewport_to_world(camera_transform, window.cursor_position()?)?;
+    let distance = ray.intersect_plane(origin, Vec3::Y)?;
+    Some(ray.get_point(distance))
 }
 
 fn spaceship_weapon_control(
-    mut commands: Commands,
-    query: Query<&Transform, With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
-    scene_assets: Res<SceneAssets>,
+    mut query: Query<&mut Weapon, With<Spaceship>>,
+    actions: Res<ActionState>,
 ) {
-    let Ok(transform) = query.get_single() else {
+    let Ok(mut weapon) = query.get_single_mut() else {
         return;
     };
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        commands.spawn((
-            MovingObjectBundle {
-                velocity: Velocity::new(-transform.forward() * MISSILE_SPEED),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::new(MISSILE_RADIUS),
-                status: Status::new(MISSILE_HEALTH, 0),
-                model: SceneBundle {
-                    scene: scene_assets.missiles.clone(),
-                    transform: Transform::from_translation(
-                        transform.translation - transform.forward() * MISSILE_FORWARD_SPAWN_SCALAR,
-                    ),
-                    ..default()
-                },
-            },
-            SpaceshipMissile,
-        ));
+    weapon.trigger_held = actions.pressed(Action::Fire);
+    if actions.just_pressed(Action::Fire) {
+        weapon.pull_trigger();
+    }
+    for (slot, stats) in Action::SLOTS.into_iter().zip(WEAPON_ROSTER) {
+        if actions.just_pressed(slot) && weapon.stats.name != stats.name {
+            weapon.switch_to(stats);
+            info!("Switched to the {}", stats.name);
+        }
+    }
+    if actions.just_pressed(Action::Reload) {
+        weapon.reload();
+    }
+    if actions.just_pressed(Action::ToggleFireMode) {
+        weapon.stats.fire_mode = match weapon.stats.fire_mode {
+            FireMode::Automatic => FireMode::SemiAutomatic,
+            FireMode::SemiAutomatic => FireMode::Automatic,
+        };
+        info!("Switched to {:?} fire", weapon.stats.fire_mode);
     }
 }
 
 fn spaceship_shield_control(
+    mut query: Query<&mut Shield, With<Spaceship>>,
+    actions: Res<ActionState>,
+) {
+    let Ok(mut shield) = query.get_single_mut() else {
+        return;
+    };
+    shield.active = actions.pressed(Action::Shield);
+}
+
+fn spaceship_dash_control(
     mut commands: Commands,
-    query: Query<Entity, With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(Entity, &Transform, &mut Dash), With<Spaceship>>,
+    actions: Res<ActionState>,
 ) {
-    let Ok(spaceship) = query.get_single() else {
+    let Ok((spaceship, transform, mut dash)) = query.get_single_mut() else {
         return;
     };
-    if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        commands.entity(spaceship).insert(SpaceshipShield);
+    if actions.just_pressed(Action::Dash) && dash.start(-transform.forward()) {
+        commands
+            .entity(spaceship)
+            .insert(Invulnerable::new(DASH_INVULNERABILITY_SECONDS));
+    }
+}
+
+/// Integrates the pull of the gravity wells into the arcade spaceship's drift, every
+/// tick like the other moving objects.
+fn update_gravity_drift(
+    mut query: Query<(&Gravity, &mut GravityDrift)>,
+    flight_model: Res<FlightModel>,
+    time: Res<Time>,
+) {
+    for (gravity, mut gravity_drift) in query.iter_mut() {
+        gravity_drift.value = match *flight_model {
+            FlightModel::Arcade => {
+                (gravity_drift.value + gravity.value * time.delta_seconds())
+                    * (-ARCADE_GRAVITY_DRAG * time.delta_seconds()).exp()
+            }
+            // the velocity carries the pull
+            FlightModel::Inertial => Vec3::ZERO,
+        };
+    }
+}
+
+fn follow_spaceship(
+    mut camera_query: Query<&mut Transform, (With<SpaceshipCamera>, Without<Spaceship>)>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
+) {
+    let (Ok(mut camera_transform), Ok(spaceship_transform)) =
+        (camera_query.get_single_mut(), spaceship_query.get_single())
+    else {
+        return;
+    };
+    // the same as when the camera was a child of the spaceship, rolling with it in the plane too
+    camera_transform.translation = spaceship_transform.translation
+        + spaceship_transform.rotation * SPACESHIP_CAMERA_TRANSLATION;
+    camera_transform.look_at(spaceship_transform.translation, spaceship_transform.up());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn back_to_planar_flight_lands_on_the_plane() {
+        let mut world = World::new();
+        let mut actions = ActionState::default();
+        actions.press(Action::ToggleFlightDimensions);
+        world.insert_resource(actions);
+        world.insert_resource(FlightDimensions::Full3d);
+        world.init_resource::<Time<Fixed>>();
+        let spaceship = world
+            .spawn((
+                Transform::from_xyz(3., 5., 4.),
+                Velocity::new(Vec3::new(1., 2., 0.)),
+                Acceleration::new(Vec3::ZERO),
+                AngularVelocity::new(Vec3::ZERO),
+                Interpolated::default(),
+                Spaceship,
+            ))
+            .id();
+        // a tick up in 3D
+        world.run_system_once(interpolation::restore_simulation_state);
+        world.run_system_once(interpolation::store_simulation_state);
+
+        // switched between the ticks, then drawn and ticked again
+        world.run_system_once(switch_flight_dimensions);
+        world.run_system_once(interpolation::interpolate_transforms);
+        world.run_system_once(interpolation::restore_simulation_state);
+
+        assert_eq!(
+            *world.resource::<FlightDimensions>(),
+            FlightDimensions::Planar
+        );
+        let transform = world.get::<Transform>(spaceship).unwrap();
+        assert_eq!(transform.translation, Vec3::new(3., 0., 4.));
+        assert_eq!(world.get::<Velocity>(spaceship).unwrap().value.y, 0.);
     }
 }
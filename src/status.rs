@@ -1,6 +1,16 @@
 use bevy::prelude::*;
 
-use crate::{gamestate::GameState, spaceship::Spaceship};
+use crate::{
+    controls::{Action, ActionState},
+    damage::{DeathEvent, Team},
+    dash::Dash,
+    gamestate::{GameState, Pause},
+    schedule::InGameSet,
+    shield::Shield,
+    ship_selection::ShipMenu,
+    spaceship::Spaceship,
+    weapon::Weapon,
+};
 
 #[derive(Component, Debug, Default)]
 pub struct Status {
@@ -14,22 +24,44 @@ impl Status {
     }
 }
 
+/// How many more times the entity can die.
+#[derive(Component, Debug)]
+pub struct Lives {
+    pub value: u32,
+}
+
+impl Lives {
+    pub fn new(value: u32) -> Self {
+        Self { value }
+    }
+}
+
 pub struct StatusPlugin;
 
 impl Plugin for StatusPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, spawn_spaceship_status)
-            .add_systems(Update, display_spaceship_status);
+        app.add_systems(
+            FixedUpdate,
+            score_kills
+                .in_set(InGameSet::DespawnEntities)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        )
+        .add_systems(Update, (spawn_spaceship_status, display_spaceship_status));
     }
 }
 
-fn spawn_spaceship_status(mut commands: Commands, query: Query<&Status, With<Spaceship>>) {
-    if let Ok(status) = query.get_single() {
+#[allow(clippy::type_complexity)]
+fn spawn_spaceship_status(
+    mut commands: Commands,
+    // the spaceship is only spawned once a ship has been picked
+    query: Query<(&Status, &Lives, &Shield, &Weapon, &Dash), Added<Spaceship>>,
+) {
+    if let Ok((status, lives, shield, weapon, dash)) = query.get_single() {
         commands.spawn(TextBundle {
             text: Text {
                 sections: vec![
                     TextSection {
-                        value: format!("Health: {:?} ", status.health),
+                        value: format!("Lives: {:?} Health: {:?} ", lives.value, status.health),
                         style: TextStyle {
                             font: Handle::default(),
                             font_size: 20.0,
@@ -37,7 +69,31 @@ fn spawn_spaceship_status(mut commands: Commands, query: Query<&Status, With<Spa
                         },
                     },
                     TextSection {
-                        value: format!("Score: {:?}", status.score),
+                        value: format!("Score: {:?} ", status.score),
+                        style: TextStyle {
+                            font: Handle::default(),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: weapon_status(weapon),
+                        style: TextStyle {
+                            font: Handle::default(),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: shield_status(shield),
+                        style: TextStyle {
+                            font: Handle::default(),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: dash_status(dash),
                         style: TextStyle {
                             font: Handle::default(),
                             font_size: 20.0,
@@ -52,16 +108,70 @@ fn spawn_spaceship_status(mut commands: Commands, query: Query<&Status, With<Spa
     }
 }
 
+fn score_kills(
+    mut death_events: EventReader<DeathEvent>,
+    query: Query<(&Status, &Team), Without<Spaceship>>,
+    team_query: Query<&Team>,
+    mut spaceship_query: Query<&mut Status, With<Spaceship>>,
+) {
+    let Ok(mut spaceship_status) = spaceship_query.get_single_mut() else {
+        return;
+    };
+    for event in death_events.read() {
+        // only the player's kills are worth anything
+        if team_query.get(event.killer) != Ok(&Team::Player) {
+            continue;
+        }
+        if let Ok((status, Team::Enemy)) = query.get(event.entity) {
+            spaceship_status.score += status.score;
+        }
+    }
+}
+
+fn weapon_status(weapon: &Weapon) -> String {
+    if weapon.is_reloading() {
+        format!("{}: reloading ", weapon.stats.name)
+    } else {
+        format!(
+            "{}: {}/{} ",
+            weapon.stats.name, weapon.ammo, weapon.stats.ammo_capacity
+        )
+    }
+}
+
+fn shield_status(shield: &Shield) -> String {
+    format!(
+        "Shield: {:.0}% Absorbed: {:?} ",
+        100.0 * shield.energy / shield.max_energy,
+        shield.absorbed
+    )
+}
+
+fn dash_status(dash: &Dash) -> String {
+    if dash.is_ready() {
+        "Dash: ready".to_string()
+    } else {
+        format!("Dash: {:.1}s", dash.cooldown.remaining_secs())
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn display_spaceship_status(
-    mut query: Query<(&mut Text, &mut Visibility), Without<GameState>>,
-    query_spaceship: Query<&Status, With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(&mut Text, &mut Visibility), (Without<GameState>, Without<ShipMenu>)>,
+    query_spaceship: Query<(&Status, &Lives, &Shield, &Weapon, &Dash), With<Spaceship>>,
+    actions: Res<ActionState>,
 ) {
-    if let Ok(spaceship_status) = query_spaceship.get_single() {
+    if let Ok((spaceship_status, lives, shield, weapon, dash)) = query_spaceship.get_single() {
         if let Ok((mut text, mut visibility)) = query.get_single_mut() {
-            text.sections[0].value = format!("Health: {:?} ", spaceship_status.health);
-            text.sections[1].value = format!("Score: {:?}", spaceship_status.score);
-            if keyboard_input.just_pressed(KeyCode::V) {
+            text.sections[0].value = format!(
+                "Lives: {:?} Health: {:?} ",
+                lives.value, spaceship_status.health
+            );
+            text.sections[1].value = format!("Score: {:?} ", spaceship_status.score);
+            text.sections[2].value = weapon_status(weapon);
+            text.sections[3].value = shield_status(shield);
+            text.sections[4].value = dash_status(dash);
+            if actions.just_pressed(Action::ToggleHud) {
                 if *visibility == Visibility::Hidden {
                     *visibility = Visibility::Inherited;
                 } else {
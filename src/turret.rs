@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision_detection::ColliderOverlay, gamestate::Pause, schedule::InGameSet, weapon::Weapon,
+};
+
+// on top of the hull
+const TURRET_OFFSET: Vec3 = Vec3::new(0., 1.5, 0.);
+const TURRET_SIZE: Vec3 = Vec3::new(0.8, 0.8, 4.0);
+const TURRET_COLOR: Color = Color::rgb(0.7, 0.7, 0.75);
+
+/// Aims the entity's weapon on its own, so the hull can keep its heading.
+#[derive(Component, Debug)]
+pub struct Turret {
+    /// In radians per second.
+    pub turn_rate: f32,
+    /// Where to aim, along the hull's nose when there's nothing to aim at.
+    pub target: Option<Vec3>,
+    /// Where the turret points, in world space.
+    direction: Option<Vec3>,
+}
+
+impl Turret {
+    pub fn new(turn_rate: f32) -> Self {
+        Self {
+            turn_rate,
+            target: None,
+            direction: None,
+        }
+    }
+}
+
+/// The barrel drawn for a `Turret`, a child of the entity with the turret.
+#[derive(Component, Debug)]
+pub struct TurretModel;
+
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            aim_turrets
+                .in_set(InGameSet::EntityUpdates)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        )
+        .add_systems(Update, (spawn_turret_models, update_turret_models).chain());
+    }
+}
+
+/// Turns the turrets towards their target, and points the weapon where the turret points.
+fn aim_turrets(mut query: Query<(&Transform, &mut Turret, &mut Weapon)>, time: Res<Time>) {
+    for (transform, mut turret, mut weapon) in query.iter_mut() {
+        let Some(target) = turret.target else {
+            // back to firing along the nose
+            turret.direction = None;
+            weapon.aim = None;
+            continue;
+        };
+        let nose = transform.rotation * Vec3::Z;
+        let heading = turret.direction.unwrap_or(nose);
+        let Some(to_target) = (target - transform.translation).try_normalize() else {
+            continue;
+        };
+        // the same turn as the homing missiles, capped by the turn rate
+        let angle = heading.angle_between(to_target);
+        let max_angle = turret.turn_rate * time.delta_seconds();
+        let turn = Quat::from_rotation_arc(heading, to_target);
+        let turn = Quat::IDENTITY.slerp(turn, (max_angle / angle).min(1.0));
+        let direction = (turn * heading).normalize();
+        turret.direction = Some(direction);
+        weapon.aim = Some(direction);
+    }
+}
+
+fn spawn_turret_models(
+    mut commands: Commands,
+    query: Query<Entity, Added<Turret>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in query.iter() {
+        let model = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Box::new(
+                        TURRET_SIZE.x,
+                        TURRET_SIZE.y,
+                        TURRET_SIZE.z,
+                    ))),
+                    material: materials.add(StandardMaterial {
+                        base_color: TURRET_COLOR,
+                        ..default()
+                    }),
+                    transform: Transform::from_translation(TURRET_OFFSET),
+                    ..default()
+                },
+                TurretModel,
+                ColliderOverlay,
+            ))
+            .id();
+        commands.entity(entity).add_child(model);
+    }
+}
+
+fn update_turret_models(
+    mut model_query: Query<(&Parent, &mut Transform), With<TurretModel>>,
+    query: Query<(&Transform, &Turret), Without<TurretModel>>,
+) {
+    for (parent, mut transform) in model_query.iter_mut() {
+        let Ok((parent_transform, turret)) = query.get(parent.get()) else {
+            continue;
+        };
+        // the model is in the hull's space, the turret's direction in the world's
+        transform.rotation = match turret.direction {
+            Some(direction) => {
+                parent_transform.rotation.inverse() * Quat::from_rotation_arc(Vec3::Z, direction)
+            }
+            None => Quat::IDENTITY,
+        };
+        transform.translation = TURRET_OFFSET + transform.rotation * Vec3::Z * TURRET_SIZE.z * 0.5;
+    }
+}
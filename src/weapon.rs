@@ -0,0 +1,447 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    asset_loader::SceneAssets,
+    collider_shape::ColliderShape,
+    collision_detection::{Ccd, Collider, CollisionLayer},
+    damage::{Damage, HitEvent, Team},
+    despawn::{Lifetime, MaxRange},
+    gamestate::Pause,
+    interpolation::Interpolated,
+    movement::{Acceleration, Gravity, MovingObjectBundle, Velocity},
+    schedule::InGameSet,
+    status::Status,
+};
+
+// far enough in front of the shooter not to hit it
+const PROJECTILE_FORWARD_SPAWN_SCALAR: f32 = 7.5;
+const PROJECTILE_RADIUS: f32 = 1.0;
+const PROJECTILE_HEALTH: u32 = 1;
+// the homing missiles can circle for a while without getting far
+const PROJECTILE_LIFETIME_SECONDS: f32 = 5.0;
+const PROJECTILE_MAX_RANGE: f32 = 150.0;
+const LASER_COLOR: Color = Color::ORANGE_RED;
+// thin, but thick enough to touch the shapes it goes through
+const LASER_BEAM_RADIUS: f32 = 0.05;
+// how many halvings to find where the beam enters a shape
+const LASER_ENTRY_ITERATIONS: usize = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FireMode {
+    /// Keeps firing while the trigger is held.
+    #[default]
+    Automatic,
+    /// Fires once each time the trigger is pulled.
+    SemiAutomatic,
+}
+
+/// What comes out of a weapon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeaponKind {
+    /// `count` projectiles per shot, fanned out evenly over `fan` radians.
+    Projectiles { count: u32, fan: f32 },
+    /// Projectiles turning towards the nearest enemy by up to `turn_rate` radians per second.
+    Homing { turn_rate: f32 },
+    /// A beam hitting the first thing within `range`. Each shot is one tick of
+    /// damage, so it hurts for as long as the trigger is held.
+    Laser { range: f32 },
+}
+
+/// What a weapon can do, shared by every weapon of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeaponStats {
+    pub name: &'static str,
+    pub kind: WeaponKind,
+    /// Shots per second.
+    pub fire_rate: f32,
+    pub fire_mode: FireMode,
+    pub ammo_capacity: u32,
+    pub reload_seconds: f32,
+    pub projectile_speed: f32,
+    pub damage: u32,
+    /// The largest angle in radians a shot can stray from where the weapon points.
+    pub spread: f32,
+}
+
+/// Fires along the entity's nose (its local +Z), unless aimed elsewhere.
+#[derive(Component, Debug)]
+pub struct Weapon {
+    pub stats: WeaponStats,
+    pub ammo: u32,
+    /// Set by the controls.
+    pub trigger_held: bool,
+    /// The direction to fire in instead of the nose, set by the controls or a turret.
+    pub aim: Option<Vec3>,
+    /// The laser beam from the last tick, for drawing it.
+    pub beam: Option<(Vec3, Vec3)>,
+    /// Remembers a trigger pull until it is fired, even if it was released before the next tick.
+    trigger_pulled: bool,
+    /// Seconds until the next shot.
+    cooldown: f32,
+    /// Seconds until the weapon is reloaded, if it's reloading.
+    reloading: Option<f32>,
+}
+
+impl Weapon {
+    pub fn new(stats: WeaponStats) -> Self {
+        Self {
+            stats,
+            ammo: stats.ammo_capacity,
+            trigger_held: false,
+            aim: None,
+            beam: None,
+            trigger_pulled: false,
+            cooldown: 0.0,
+            reloading: None,
+        }
+    }
+
+    /// Switches to another weapon, which has to be loaded before it can fire.
+    pub fn switch_to(&mut self, stats: WeaponStats) {
+        *self = Self::new(stats);
+        self.ammo = 0;
+        self.reload();
+    }
+
+    pub fn pull_trigger(&mut self) {
+        self.trigger_pulled = true;
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reloading.is_some()
+    }
+
+    /// Starts reloading, unless already reloading or full.
+    pub fn reload(&mut self) {
+        if self.reloading.is_none() && self.ammo < self.stats.ammo_capacity {
+            self.reloading = Some(self.stats.reload_seconds);
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Projectile;
+
+#[derive(Component, Debug)]
+pub struct Homing {
+    /// In radians per second.
+    pub turn_rate: f32,
+}
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (fire_weapons, steer_homing_projectiles)
+                .in_set(InGameSet::EntityUpdates)
+                .run_if(|pause: Res<Pause>| !pause.is_paused),
+        )
+        .add_systems(Update, draw_laser_beams);
+    }
+}
+
+fn fire_weapons(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, &Team, &mut Weapon)>,
+    target_query: Query<(Entity, &Transform, &Collider)>,
+    mut hit_events: EventWriter<HitEvent>,
+    scene_assets: Res<SceneAssets>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, transform, team, mut weapon) in query.iter_mut() {
+        weapon.cooldown = (weapon.cooldown - time.delta_seconds()).max(0.0);
+        if let Some(remaining) = weapon.reloading {
+            let remaining = remaining - time.delta_seconds();
+            if remaining > 0.0 {
+                weapon.reloading = Some(remaining);
+            } else {
+                weapon.reloading = None;
+                weapon.ammo = weapon.stats.ammo_capacity;
+            }
+        }
+
+        let pulled = match weapon.stats.fire_mode {
+            FireMode::Automatic => weapon.trigger_held || weapon.trigger_pulled,
+            FireMode::SemiAutomatic => weapon.trigger_pulled,
+        };
+        let loaded = !weapon.is_reloading() && weapon.ammo > 0;
+        let forward = weapon.aim.unwrap_or(-transform.forward());
+        let origin = transform.translation + forward * PROJECTILE_FORWARD_SPAWN_SCALAR;
+
+        // the beam stays on between the ticks of damage
+        weapon.beam = None;
+        let mut laser_target = None;
+        if let (WeaponKind::Laser { range }, true, true) = (weapon.stats.kind, pulled, loaded) {
+            let (_, filter) = projectile_layers(*team);
+            let hit = raycast(origin, forward, range, filter, &target_query);
+            let length = hit.map_or(range, |(_, distance)| distance);
+            weapon.beam = Some((origin, origin + forward * length));
+            laser_target = hit.map(|(target, _)| target);
+        }
+
+        if !pulled || !loaded || weapon.cooldown > 0.0 {
+            continue;
+        }
+        weapon.trigger_pulled = false;
+        weapon.ammo -= 1;
+        weapon.cooldown = 1.0 / weapon.stats.fire_rate;
+        if weapon.ammo == 0 {
+            weapon.reload();
+        }
+
+        // spread sideways, which stays in the plane unless flying in 3D
+        let side_axis = forward
+            .cross(Vec3::Y)
+            .cross(forward)
+            .try_normalize()
+            .unwrap_or(Vec3::Y);
+        let aim = |angle: f32| Quat::from_axis_angle(side_axis, angle) * forward;
+        let stray = rng.gen_range(-1.0..=1.0) * weapon.stats.spread;
+
+        match weapon.stats.kind {
+            WeaponKind::Projectiles { count, fan } => {
+                for i in 0..count {
+                    let angle = if count > 1 {
+                        fan * (i as f32 / (count - 1) as f32 - 0.5)
+                    } else {
+                        0.0
+                    };
+                    spawn_projectile(
+                        &mut commands,
+                        &scene_assets,
+                        origin,
+                        aim(angle + stray) * weapon.stats.projectile_speed,
+                        weapon.stats.damage,
+                        *team,
+                    );
+                }
+            }
+            WeaponKind::Homing { turn_rate } => {
+                let projectile = spawn_projectile(
+                    &mut commands,
+                    &scene_assets,
+                    origin,
+                    aim(stray) * weapon.stats.projectile_speed,
+                    weapon.stats.damage,
+                    *team,
+                );
+                commands.entity(projectile).insert(Homing { turn_rate });
+            }
+            WeaponKind::Laser { .. } => {
+                if let Some(target) = laser_target {
+                    hit_events.send(HitEvent {
+                        target,
+                        source: entity,
+                        amount: weapon.stats.damage,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Which layers the shots of a team are on and which ones they hit.
+fn projectile_layers(team: Team) -> (CollisionLayer, CollisionLayer) {
+    match team {
+        Team::Player => (
+            CollisionLayer::PLAYER_PROJECTILE,
+            CollisionLayer::ENEMY | CollisionLayer::ENVIRONMENT,
+        ),
+        Team::Enemy | Team::Neutral => (
+            CollisionLayer::ENEMY_PROJECTILE,
+            CollisionLayer::PLAYER | CollisionLayer::ENVIRONMENT,
+        ),
+    }
+}
+
+/// The closest collider on the `filter` layers along the ray and its distance,
+/// checking the bounding spheres first and then the colliders' shapes.
+fn raycast(
+    origin: Vec3,
+    direction: Vec3,
+    range: f32,
+    filter: CollisionLayer,
+    query: &Query<(Entity, &Transform, &Collider)>,
+) -> Option<(Entity, f32)> {
+    let mut closest = None;
+    for (entity, transform, collider) in query.iter() {
+        if !collider.membership.intersects(filter) {
+            continue;
+        }
+        let offset = transform.translation - origin;
+        let along = offset.dot(direction);
+        let miss_squared = offset.length_squared() - along * along;
+        let radius_squared = collider.radius * collider.radius;
+        if miss_squared > radius_squared {
+            continue;
+        }
+        // where the ray enters the sphere, or the origin if it starts inside
+        let distance = (along - (radius_squared - miss_squared).sqrt()).max(0.0);
+        if along + collider.radius < 0.0 || distance > range {
+            continue;
+        }
+        // no further than the far side of the sphere
+        let length = range.min(along + collider.radius);
+        let Some(distance) = beam_entry(origin, direction, length, transform, collider) else {
+            continue;
+        };
+        if closest.map_or(true, |(_, closest_distance)| distance < closest_distance) {
+            closest = Some((entity, distance));
+        }
+    }
+    closest
+}
+
+/// How far along the beam it first touches the collider's shape, or `None` if it misses.
+fn beam_entry(
+    origin: Vec3,
+    direction: Vec3,
+    length: f32,
+    transform: &Transform,
+    collider: &Collider,
+) -> Option<f32> {
+    let touches = |length: f32| {
+        let beam = ColliderShape::Capsule {
+            start: Vec3::ZERO,
+            end: direction * length,
+            radius: LASER_BEAM_RADIUS,
+        };
+        beam.contact(
+            origin,
+            Quat::IDENTITY,
+            &collider.shape,
+            transform.translation,
+            transform.rotation,
+        )
+        .is_some()
+    };
+    if !touches(length) {
+        return None;
+    }
+    // the shorter beams starting at the same point only touch it past the entry
+    let (mut outside, mut inside) = (0.0, length);
+    for _ in 0..LASER_ENTRY_ITERATIONS {
+        let middle = (outside + inside) / 2.0;
+        if touches(middle) {
+            inside = middle;
+        } else {
+            outside = middle;
+        }
+    }
+    Some(inside)
+}
+
+fn spawn_projectile(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    translation: Vec3,
+    velocity: Vec3,
+    damage: u32,
+    team: Team,
+) -> Entity {
+    let (membership, filter) = projectile_layers(team);
+    commands
+        .spawn((
+            MovingObjectBundle {
+                velocity: Velocity::new(velocity),
+                acceleration: Acceleration::new(Vec3::ZERO),
+                gravity: Gravity::default(),
+                collider: Collider::new(PROJECTILE_RADIUS).with_layers(membership, filter),
+                status: Status::new(PROJECTILE_HEALTH, 0),
+                model: SceneBundle {
+                    scene: scene_assets.missiles.clone(),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                interpolated: Interpolated::default(),
+            },
+            // projectiles are fast enough to fly through an asteroid in a single frame
+            Ccd::default(),
+            Lifetime::new(PROJECTILE_LIFETIME_SECONDS),
+            MaxRange::new(translation, PROJECTILE_MAX_RANGE),
+            Damage::new(damage),
+            team,
+            Projectile,
+        ))
+        .id()
+}
+
+#[allow(clippy::type_complexity)]
+fn steer_homing_projectiles(
+    mut query: Query<(&Transform, &mut Velocity, &Team, &Homing)>,
+    target_query: Query<(&Transform, &Team), (With<Status>, Without<Projectile>)>,
+    time: Res<Time>,
+) {
+    for (transform, mut velocity, team, homing) in query.iter_mut() {
+        let nearest = target_query
+            .iter()
+            .filter(|(_, target_team)| *target_team != team && **target_team != Team::Neutral)
+            .map(|(target_transform, _)| target_transform.translation)
+            .min_by(|a, b| {
+                a.distance_squared(transform.translation)
+                    .total_cmp(&b.distance_squared(transform.translation))
+            });
+        let Some(target) = nearest else {
+            continue;
+        };
+
+        let (Some(heading), Some(to_target)) = (
+            velocity.value.try_normalize(),
+            (target - transform.translation).try_normalize(),
+        ) else {
+            continue;
+        };
+        // turn towards the target, but no faster than the turn rate
+        let angle = heading.angle_between(to_target);
+        let max_angle = homing.turn_rate * time.delta_seconds();
+        let turn = Quat::from_rotation_arc(heading, to_target);
+        let turn = Quat::IDENTITY.slerp(turn, (max_angle / angle).min(1.0));
+        velocity.value = turn * velocity.value;
+    }
+}
+
+fn draw_laser_beams(mut gizmos: Gizmos, query: Query<&Weapon>) {
+    for weapon in query.iter() {
+        if let Some((start, end)) = weapon.beam {
+            gizmos.line(start, end, LASER_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beam_enters_where_the_shape_starts() {
+        let collider = Collider::new(2.0);
+        let transform = Transform::from_xyz(0., 0., 10.);
+
+        let distance = beam_entry(Vec3::ZERO, Vec3::Z, 20.0, &transform, &collider).unwrap();
+
+        assert!((distance - (8.0 - LASER_BEAM_RADIUS)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn beam_through_the_bounding_sphere_can_miss_the_shape() {
+        // a long capsule along X, its bounding sphere is much larger than it
+        let collider = Collider::from_shape(ColliderShape::Capsule {
+            start: Vec3::new(-5., 0., 0.),
+            end: Vec3::new(5., 0., 0.),
+            radius: 1.0,
+        });
+        let transform = Transform::IDENTITY;
+        let origin = Vec3::new(3., 3., -20.);
+
+        assert!(origin.distance(Vec3::new(0., 0., -20.)) < collider.radius);
+        assert!(beam_entry(origin, Vec3::Z, 40.0, &transform, &collider).is_none());
+        // but not the capsule itself
+        let origin = Vec3::new(3., 0.5, -20.);
+        assert!(beam_entry(origin, Vec3::Z, 40.0, &transform, &collider).is_some());
+    }
+}